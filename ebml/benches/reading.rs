@@ -0,0 +1,84 @@
+//! Benchmarks of reading documents: skipping a large element, and parsing many small ones. Run
+//! them with `cargo bench`.
+
+extern crate ebml;
+
+use std::env;
+use std::fs::{self, File};
+use std::io::{BufReader, Cursor, Read};
+use std::time::Instant;
+
+use ebml::events::Parser;
+use ebml::read::read_document;
+use ebml::{Id, Seekable};
+
+const HEADER: &[u8] = include_bytes!("../tests/min_valid_header");
+
+// A source which can't seek, like a pipe.
+struct Unseekable<'a>(&'a [u8]);
+impl<'a> Read for Unseekable<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+// A document holding a single `Void` element with the given amount of data.
+fn void_document(len: u64) -> Vec<u8> {
+    let mut data = HEADER.to_vec();
+    data.extend_from_slice(&[0xEC, 0x01]);
+    data.extend_from_slice(&len.to_be_bytes()[1..]);
+    data.resize(data.len() + len as usize, 0);
+    data
+}
+
+// A document holding the given number of small elements.
+fn small_elements_document(count: usize) -> Vec<u8> {
+    let mut data = HEADER.to_vec();
+    for _ in 0..count {
+        data.extend_from_slice(&[0x42, 0x86, 0x81, 0x01]);
+    }
+    data
+}
+
+// Runs `f` the given number of times after warming up, and prints the average time it took.
+fn bench<F: FnMut()>(name: &str, iterations: u32, mut f: F) {
+    f();
+    let start = Instant::now();
+    for _ in 0..iterations {
+        f();
+    }
+    println!("{:<48} {:>12.3?}", name, start.elapsed() / iterations);
+}
+
+fn main() {
+    let data = void_document(100 << 20);
+    bench("skip 100 MB element, Cursor", 10, || {
+        let (_, mut body) = read_document(Cursor::new(&data[..]), 2).unwrap();
+        assert!(body.skip_element().unwrap());
+    });
+    bench("skip 100 MB element, BufReader", 10, || {
+        let source = BufReader::new(Unseekable(&data));
+        let (_, mut body) = read_document(source, 2).unwrap();
+        assert!(body.skip_element().unwrap());
+    });
+    let path = env::temp_dir().join("ebml-bench-void.mkv");
+    fs::write(&path, &data).unwrap();
+    bench("skip 100 MB element, Seekable<BufReader<File>>", 10, || {
+        let source = Seekable::new(BufReader::new(File::open(&path).unwrap())).unwrap();
+        let (_, mut body) = read_document(source, 2).unwrap();
+        assert!(body.skip_element().unwrap());
+    });
+    fs::remove_file(&path).unwrap();
+
+    let data = small_elements_document(1 << 20);
+    let header = Id::from_encoded(0x1A45_DFA3).unwrap();
+    bench("parse 1M small elements, Cursor", 10, || {
+        let parser = Parser::new(Cursor::new(&data[..]), |id: &Id| *id == header).unwrap();
+        assert_eq!((1 << 20) + 9, parser.count());
+    });
+    bench("parse 1M small elements, BufReader", 10, || {
+        let source = BufReader::new(Unseekable(&data));
+        let parser = Parser::new(source, |id: &Id| *id == header).unwrap();
+        assert_eq!((1 << 20) + 9, parser.count());
+    });
+}
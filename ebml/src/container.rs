@@ -6,14 +6,28 @@
 
 //! EBML containers, which are values containing a list of more elements.
 
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::{self, Write};
 use std::marker::PhantomData;
 use std::ops::Add;
+use std::rc::Rc;
+use std::sync::{Arc, OnceLock};
 
 use typenum;
+use typenum::Integer;
 
-use {cardinality, Id, EbmlResult, child_order, Size, AnyLevel, AnyContainer};
-use element::Element;
-use std_containers::EbmlHeader;
+use {cardinality, Id, EbmlResult, EbmlValue, child_order, Size, AnyLevel, AnyContainer, Exceeds,
+     MaxLevel, UintValue, UNKNOWN_SIZE};
+use cardinality::Cardinality;
+use element::{check_restrictions, Element};
+use error::{EbmlError, ErrorContext};
+use limits::{self, Limits, Validation};
+use source::Source;
+use read::{is_crc32_container, read_root};
+use crc32;
+use std_containers::{Crc32Container, EbmlHeader};
+use std_elems::{Crc32, Crc32Value, Void};
 
 /// Implement this trait on an empty enum for each container type in your document.
 pub trait Container {
@@ -37,9 +51,8 @@ pub trait Container {
     /// The maximum possible nesting level of containers of this type. If this type is not
     /// restricted by its parent container, set this to the special type `AnyLevel`.
     ///
-    /// If there should be no maximum, set this to a large value. It is recommended to use
-    /// `typenum::P8192`.
-    type MaxAllowedLevel: typenum::Integer;
+    /// If there should be no maximum, set this to the special type `Unbounded`.
+    type MaxAllowedLevel: MaxLevel;
 
     /// The name of the container type. This is a symbolic identifier for the container, and the
     /// set of element and container names must have a 1-to-1 mapping onto the set of element and
@@ -53,14 +66,706 @@ pub trait Container {
     /// mainly the lack of `const fn` and the fact that `Size` allocates from the heap internally
     /// (which may be changed eventually).
     fn get_id() -> Id;
+
+    /// Gets the types of the elements and containers which may occur as children of containers
+    /// of this type, including those which are restricted by nesting level rather than parent.
+    /// `Void` and `Crc32` elements may occur in every container, and don't have to be listed.
+    ///
+    /// Readers only call this once per thread for each container type, and keep the list.
+    fn child_types() -> Vec<ChildType>;
+
+    /// Checks whether an element with the given ID may occur as a child of containers of this
+    /// type. This is used to find the end of containers with unknown sizes: such a container ends
+    /// at the first element which is not a valid child.
+    ///
+    /// The default implementation accepts `Void` and `Crc32` elements, and the children listed by
+    /// `child_types`.
+    fn may_contain(id: &Id) -> bool {
+        *id == Void::get_id() ||
+            *id == Crc32::get_id() ||
+            child_types_of::<Self>().iter().any(|child| child.id == *id)
+    }
+
+    /// Gets the IDs and names of the children which must occur in containers of this type, and
+    /// which don't have a default value. Containers which are built or written without one of
+    /// them are rejected.
+    ///
+    /// The default implementation returns the children listed by `child_types` which are required.
+    fn required_children() -> Vec<(Id, &'static str)> {
+        Self::child_types()
+            .into_iter()
+            .filter(|child| child.required)
+            .map(|child| (child.id, child.name))
+            .collect()
+    }
+}
+
+/// A type of element or container which may occur as a child of a container, as listed by
+/// `Container::child_types`.
+#[derive(Debug, Clone)]
+pub struct ChildType {
+    /// The ID of the child type.
+    pub id: Id,
+    /// The name of the child type.
+    pub name: &'static str,
+    /// Whether the child must occur: its cardinality is `ExactlyOne` or `OneOrMany`, and it
+    /// doesn't have a default value.
+    pub required: bool,
+    // How to find the end of children of this type with an unknown size, if they are containers.
+    pub(crate) nested: Option<Nested>,
+}
+impl ChildType {
+    /// Describes the element type `T`.
+    pub fn element<T: Element>() -> Self {
+        ChildType {
+            id: T::get_id(),
+            name: T::NAME,
+            required: T::Cardinality::AT_LEAST_ONCE && T::DEFAULT_VALUE.is_none(),
+            nested: None,
+        }
+    }
+
+    /// Describes the container type `NC`.
+    pub fn container<NC: Container>() -> Self {
+        ChildType {
+            id: NC::get_id(),
+            name: NC::NAME,
+            required: NC::Cardinality::AT_LEAST_ONCE,
+            nested: Some(Nested {
+                may_contain: NC::may_contain,
+                child_types: child_types_of::<NC>,
+                restricted: restricted_by_level::<NC>(),
+            }),
+        }
+    }
+}
+
+/// The functions of a container type which find the end of a container of that type with an
+/// unknown size: it ends at the first element which `may_contain` rejects, and the child types are
+/// needed to find the end of its own children with unknown sizes.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Nested {
+    pub(crate) may_contain: fn(&Id) -> bool,
+    pub(crate) child_types: fn() -> Rc<Vec<ChildType>>,
+    // Whether the container is restricted by nesting level, so that it may not have an unknown
+    // size.
+    pub(crate) restricted: bool,
+}
+
+/// Looks up the child type with the given ID, which has an unknown size, and returns how to find
+/// its end. Fails if it isn't a container which may have an unknown size. `offset` is the position
+/// of the child, which is recorded in the error.
+pub(crate) fn nested_child(child_types: &[ChildType], id: &Id, offset: u64) -> EbmlResult<Nested> {
+    let child = child_types.iter().find(|child| child.id == *id);
+    match child.and_then(|child| child.nested.map(|nested| (child.name, nested))) {
+        Some((_, nested)) if !nested.restricted => Ok(nested),
+        Some((name, _)) => {
+            let context = ErrorContext::at(offset).actual(id.clone());
+            Err(EbmlError::UnknownSizeNotAllowed(name, context))
+        }
+        None => Err(EbmlError::MalformedDocument(ErrorContext::at(offset).actual(id.clone()))),
+    }
+}
+
+thread_local! {
+    // The child types of the container types which have been looked up, keyed by the address of
+    // their `child_types` function. Types whose functions were merged list the same children.
+    static CHILD_TYPES: RefCell<HashMap<usize, Rc<Vec<ChildType>>>> = RefCell::new(HashMap::new());
+}
+
+/// Gets the child types of containers of type `C`, which are only listed once per thread. This
+/// is called for every element peeked at in a container with an unknown size.
+pub(crate) fn child_types_of<C: Container + ?Sized>() -> Rc<Vec<ChildType>> {
+    let list: fn() -> Vec<ChildType> = C::child_types;
+    let key = list as usize;
+    if let Some(types) = CHILD_TYPES.with(|cache| cache.borrow().get(&key).cloned()) {
+        return types;
+    }
+    let types = Rc::new(list());
+    CHILD_TYPES.with(|cache| cache.borrow_mut().insert(key, types.clone()));
+    types
+}
+
+/// Checks if containers of type `C` are restricted by nesting level. Such containers may not have
+/// an unknown size.
+pub(crate) fn restricted_by_level<C: Container>() -> bool {
+    C::MinAllowedLevel::to_i64() != AnyLevel::to_i64()
+}
+
+/// Checks that every child which containers of type `C` require occurs in `ids`. `path` is the
+/// path of the container, which is recorded in the error.
+pub(crate) fn check_required<'a, C, I>(ids: I, path: &[&'static str]) -> EbmlResult<()>
+where
+    C: Container,
+    I: Iterator<Item = &'a Id> + Clone,
+{
+    for (id, name) in C::required_children() {
+        if !ids.clone().any(|other| *other == id) {
+            let context = ErrorContext::default().expected(id).within(path);
+            return Err(EbmlError::CardinalityViolation(name, context));
+        }
+    }
+    Ok(())
+}
+
+/// A child of a container whose type has not been determined yet. The data of children which
+/// were read is decoded when the child is requested, either as a value or as the children of a
+/// container. The children are parsed the first time they are requested, and shared by every
+/// container they are handed out in afterwards.
+#[derive(Debug, Clone)]
+pub(crate) struct Node {
+    pub(crate) id: Id,
+    // The absolute position of the node's data in the document it was read from, or 0 if the node
+    // was built.
+    pub(crate) offset: u64,
+    // The limits of the document the node was read from, and how strictly it was checked, which
+    // apply to its children.
+    pub(crate) limits: Limits,
+    pub(crate) validation: Validation,
+    pub(crate) data: NodeData,
+}
+
+/// The data of a `Node`.
+#[derive(Debug, Clone)]
+pub(crate) enum NodeData {
+    /// The encoded data of a value, or of a container which was read along with its children once
+    /// they have been parsed.
+    Encoded(Vec<u8>, OnceLock<Arc<Vec<Node>>>),
+    /// The children of a container which was built.
+    Children(Arc<Vec<Node>>),
+}
+
+impl Node {
+    pub(crate) fn new(
+        id: Id,
+        offset: u64,
+        limits: Limits,
+        validation: Validation,
+        data: Vec<u8>,
+    ) -> Self {
+        Node {
+            id,
+            offset,
+            limits,
+            validation,
+            data: NodeData::Encoded(data, OnceLock::new()),
+        }
+    }
+
+    // Decodes the data of this node as a value, recording its position and ID in errors.
+    fn decode<V: EbmlValue>(&self) -> EbmlResult<V> {
+        let result = match self.data {
+            NodeData::Encoded(ref data, _) => V::decode(data),
+            NodeData::Children(_) => Err(EbmlError::MalformedDocument(ErrorContext::default())),
+        };
+        result.map_err(|err| err.at(self.offset).actual(&self.id))
+    }
+
+    // The children of this node, which are parsed only the first time they are requested. Children
+    // with an unknown size end before the first element that isn't one of their child types.
+    fn children(&self, child_types: &[ChildType]) -> EbmlResult<Arc<Vec<Node>>> {
+        match self.data {
+            NodeData::Encoded(ref data, ref parsed) => {
+                if let Some(children) = parsed.get() {
+                    return Ok(children.clone());
+                }
+                let children =
+                    parse_nodes(data, self.offset, self.limits, &self.validation, child_types)?;
+                Ok(parsed.get_or_init(|| Arc::new(children)).clone())
+            }
+            NodeData::Children(ref children) => Ok(children.clone()),
+        }
+    }
+
+    // The size of this node with the given length, failing if it can't be encoded.
+    fn size_of(&self, length: u64) -> EbmlResult<Size> {
+        Size::from_u64(length)
+            .ok_or_else(|| EbmlError::SizeOverflow(ErrorContext::default().actual(self.id.clone())))
+    }
+
+    // The first pass of writing: computes the sizes of this node and of every container below it
+    // in the order they are written, and returns the length of the whole encoded node.
+    fn compute_sizes(&self, sizes: &mut Vec<Size>) -> EbmlResult<u64> {
+        let length = match self.data {
+            NodeData::Encoded(ref data, _) => data.len() as u64,
+            NodeData::Children(ref children) => {
+                let index = sizes.len();
+                sizes.push(UNKNOWN_SIZE);
+                let length = compute_sizes(children, sizes)?;
+                sizes[index] = self.size_of(length)?;
+                length
+            }
+        };
+
+        let size = self.size_of(length)?;
+        Ok(self.id.get_width() as u64 + size.get_width() as u64 + length)
+    }
+
+    // The second pass of writing: writes this node using the sizes from `compute_sizes`, failing
+    // if an ID or size is wider than the limits allow.
+    fn write<'a, W, I>(&self, sizes: &mut I, target: &mut W, limits: &Limits) -> EbmlResult<()>
+    where
+        W: Write,
+        I: Iterator<Item = &'a Size>,
+    {
+        limits.check_id(&self.id)?;
+        self.id.write(target)?;
+        match self.data {
+            NodeData::Encoded(ref data, _) => {
+                let size = self.size_of(data.len() as u64)?;
+                limits.check_size(&size).map_err(|err| err.actual(&self.id))?;
+                size.write(target)?;
+                target.write_all(data)?;
+            }
+            NodeData::Children(ref children) => {
+                let size = sizes.next().expect("sizes were not computed");
+                limits.check_size(size).map_err(|err| err.actual(&self.id))?;
+                size.write(target)?;
+                for child in children.iter() {
+                    child.write(sizes, target, limits)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn compute_sizes(nodes: &[Node], sizes: &mut Vec<Size>) -> EbmlResult<u64> {
+    let mut length = 0;
+    for node in nodes {
+        length += node.compute_sizes(sizes)?;
+    }
+    Ok(length)
+}
+
+/// Splits the data of a container, which starts at the given absolute position, into its
+/// children, whose IDs and sizes must be within the given limits and whose IDs are checked as
+/// strictly as `validation` demands. If the first child is a `Crc32` element, the rest of the data
+/// is verified against it. `Void` and other `Crc32` elements are skipped. With the `legacy-crc32`
+/// feature enabled, the elements in `Crc32Container`s are verified and spliced in. Children with an
+/// unknown size must be containers among `child_types`.
+pub(crate) fn parse_nodes(
+    data: &[u8],
+    offset: u64,
+    limits: Limits,
+    validation: &Validation,
+    child_types: &[ChildType],
+) -> EbmlResult<Vec<Node>> {
+    let mut result = Vec::new();
+    let (header, covered) = verify_checksum(data, offset, limits, validation, &Crc32::get_id())?;
+    let offset = offset + header as u64;
+    parse_nodes_into(covered, offset, limits, validation, child_types, &mut result)?;
+    Ok(result)
+}
+
+/// Splits the next element, which starts at the given absolute position, off the data, returning
+/// its ID, the length of its header, its data and the rest of the data. Fails with `SizeOverflow`
+/// if the element is longer than the data, and with `MalformedDocument` if its size is unknown and
+/// it isn't a container among `child_types`. The data of a container with an unknown size ends
+/// before the first element it may not contain.
+pub(crate) fn split_element<'a>(
+    data: &'a [u8],
+    offset: u64,
+    limits: Limits,
+    validation: &Validation,
+    child_types: &[ChildType],
+) -> EbmlResult<(Id, usize, &'a [u8], &'a [u8])> {
+    let id = Id::from_slice(data).map_err(|err| err.at(offset))?;
+    validation.check_id(&id, offset)?;
+    limits.check_id(&id).map_err(|err| err.at(offset))?;
+    let size_offset = offset + id.get_width() as u64;
+    let size = Size::from_slice(&data[id.get_width()..]).map_err(|err| err.at(size_offset))?;
+    limits.check_size(&size).map_err(|err| err.at(size_offset))?;
+    let header = id.get_width() + size.get_width();
+    let length = match size.get_value() {
+        Some(length) => length,
+        None => {
+            let nested = nested_child(child_types, &id, offset)?;
+            let children = &data[header..];
+            let children_offset = offset + header as u64;
+            children_length(children, children_offset, limits, validation, nested)?
+        }
+    };
+    if length > (data.len() - header) as u64 {
+        return Err(EbmlError::SizeOverflow(ErrorContext::at(offset).actual(id)));
+    }
+
+    let (child, rest) = data[header..].split_at(length as usize);
+    Ok((id, header, child, rest))
+}
+
+// The length of the children of a container with an unknown size, which start the data at the
+// given absolute position and end before the first element the container may not contain.
+fn children_length(
+    data: &[u8],
+    offset: u64,
+    limits: Limits,
+    validation: &Validation,
+    nested: Nested,
+) -> EbmlResult<u64> {
+    let child_types = (nested.child_types)();
+    let mut rest = data;
+    while !rest.is_empty() {
+        let child_offset = offset + (data.len() - rest.len()) as u64;
+        let id = Id::from_slice(rest).map_err(|err| err.at(child_offset))?;
+        if !(nested.may_contain)(&id) && !is_crc32_container(&id) {
+            break;
+        }
+        rest = split_element(rest, child_offset, limits, validation, &child_types)?.3;
+    }
+    Ok((data.len() - rest.len()) as u64)
+}
+
+fn parse_nodes_into(
+    mut data: &[u8],
+    mut offset: u64,
+    limits: Limits,
+    validation: &Validation,
+    child_types: &[ChildType],
+    result: &mut Vec<Node>,
+) -> EbmlResult<()> {
+    let void = Void::get_id();
+    let crc32 = Crc32::get_id();
+    let crc32_container = Crc32Container::get_id();
+    while !data.is_empty() {
+        let (id, header, child, rest) =
+            split_element(data, offset, limits, validation, child_types)?;
+        let child_offset = offset + header as u64;
+        if cfg!(feature = "legacy-crc32") && id == crc32_container {
+            let (header, covered) =
+                verify_checksum(child, child_offset, limits, validation, &Crc32Value::get_id())?;
+            if header == 0 {
+                // The container doesn't start with its checksum.
+                let context = ErrorContext::at(child_offset).expected(Crc32Value::get_id());
+                return Err(EbmlError::MalformedDocument(context));
+            }
+            let covered_offset = child_offset + header as u64;
+            parse_nodes_into(covered, covered_offset, limits, validation, child_types, result)?;
+        } else if id != void && id != crc32 {
+            result.push(Node::new(id, child_offset, limits, validation.clone(), child.to_vec()));
+        }
+        offset = child_offset + child.len() as u64;
+        data = rest;
+    }
+    Ok(())
+}
+
+// If the data, which starts at the given absolute position, starts with an element holding a
+// CRC-32 checksum with the given ID, verifies the rest of the data against it. Returns the length
+// of the element, or 0 if there is none, and the rest of the data.
+fn verify_checksum<'a>(
+    data: &'a [u8],
+    offset: u64,
+    limits: Limits,
+    validation: &Validation,
+    id: &Id,
+) -> EbmlResult<(usize, &'a [u8])> {
+    if data.is_empty() || Id::from_slice(data).map_err(|err| err.at(offset))? != *id {
+        return Ok((0, data));
+    }
+
+    let (_, header, value, covered) = split_element(data, offset, limits, validation, &[])?;
+    let expected = crc32::decode(value).ok_or_else(|| {
+        EbmlError::MalformedDocument(ErrorContext::at(offset).actual(id.clone()))
+    })?;
+    if crc32::checksum(covered) != expected {
+        return Err(EbmlError::ChecksumMismatch(ErrorContext::at(offset)));
+    }
+    Ok((header + value.len(), covered))
 }
 
 /// A container containing one or more elements or containers. The second type parameter is the
 /// nesting level of the container.
-#[derive(Debug)]
+///
+/// The container holds all of its children in memory; use `ContainerReader::load` or
+/// `root_container` to load one from a document. Child containers share their children with the
+/// container they were found in, so getting them again is cheap. Containers can also be built with
+/// `new` and the `push_*` methods, and then written with known sizes to any data sink, since the
+/// sizes of all of their children are computed before anything is written.
+#[derive(Debug, Clone)]
 pub struct ContainerImpl<C: Container, L> {
     _c: PhantomData<C>,
     _l: PhantomData<L>,
+
+    children: Arc<Vec<Node>>,
+    // The names of this container and the containers enclosing it in the document it was read
+    // from, outermost first. Containers which were built only know their own name.
+    path: Vec<&'static str>,
+}
+
+impl<C: Container, L> ContainerImpl<C, L> {
+    pub(crate) fn from_nodes(children: Vec<Node>, path: Vec<&'static str>) -> Self {
+        ContainerImpl::from_shared(Arc::new(children), path)
+    }
+
+    fn from_shared(children: Arc<Vec<Node>>, path: Vec<&'static str>) -> Self {
+        ContainerImpl {
+            _c: PhantomData,
+            _l: PhantomData,
+            children,
+            path,
+        }
+    }
+
+    /// Creates an empty container.
+    pub fn new() -> Self {
+        ContainerImpl::from_nodes(Vec::new(), vec![C::NAME])
+    }
+
+    /// Returns the number of children in this container.
+    pub fn len(&self) -> Size {
+        Size::from_u64(self.children.len() as u64).expect("too many children")
+    }
+
+    /// Returns true if the container has no children.
+    pub fn is_empty(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    /// Returns the absolute position of the data of the first child with the given ID, if there is
+    /// one. Children which were built have position 0.
+    pub(crate) fn offset_of(&self, id: &Id) -> Option<u64> {
+        self.find(id).next().map(|node| node.offset)
+    }
+
+    fn find<'a>(&'a self, id: &'a Id) -> impl Iterator<Item = &'a Node> + 'a {
+        self.children.iter().filter(move |node| node.id == *id)
+    }
+
+    // An error for the second child with the given ID and name, although it may occur at most
+    // once.
+    fn repeated(&self, id: Id, name: &'static str) -> EbmlError {
+        let mut context = ErrorContext::default().within(&self.path);
+        context.offset = self.find(&id).nth(1).map(|node| node.offset);
+        EbmlError::CardinalityViolation(name, context.actual(id))
+    }
+
+    // An error for a child with the given ID and name which must occur, but is missing.
+    fn missing(&self, id: Id, name: &'static str) -> EbmlError {
+        let context = ErrorContext::default().expected(id).within(&self.path);
+        EbmlError::CardinalityViolation(name, context)
+    }
+
+    fn get_values<T: Element>(&self) -> EbmlResult<Vec<T::Value>> {
+        self.find(&T::get_id())
+            .map(|node| {
+                let value = node.decode().map_err(|err| err.within(&self.path))?;
+                check_restrictions::<T>(&value)
+                    .map_err(|err| err.at(node.offset).within(&self.path))?;
+                Ok(value)
+            })
+            .collect()
+    }
+
+    fn get_zero_or_one_value<T: Element>(&self) -> EbmlResult<Option<T::Value>> {
+        let mut values = self.get_values::<T>()?;
+        if values.len() > 1 {
+            Err(self.repeated(T::get_id(), T::NAME))
+        } else {
+            Ok(values.pop())
+        }
+    }
+
+    fn get_exactly_one_value<T: Element>(&self) -> EbmlResult<T::Value> {
+        self.get_zero_or_one_value::<T>()?
+            .or(T::DEFAULT_VALUE)
+            .ok_or_else(|| self.missing(T::get_id(), T::NAME))
+    }
+
+    fn get_one_or_many_values<T: Element>(&self) -> EbmlResult<(T::Value, Vec<T::Value>)> {
+        let mut values = self.get_values::<T>()?;
+        if values.is_empty() {
+            T::DEFAULT_VALUE
+                .map(|value| (value, Vec::new()))
+                .ok_or_else(|| self.missing(T::get_id(), T::NAME))
+        } else {
+            let first = values.remove(0);
+            Ok((first, values))
+        }
+    }
+
+    fn get_children<NC: Container, NL>(&self) -> EbmlResult<Vec<ContainerImpl<NC, NL>>> {
+        let mut path = self.path.clone();
+        path.push(NC::NAME);
+        self.find(&NC::get_id())
+            .map(|node| match node.children(&child_types_of::<NC>()) {
+                Ok(children) => Ok(ContainerImpl::from_shared(children, path.clone())),
+                Err(err) => Err(err.at(node.offset).within(&path)),
+            })
+            .collect()
+    }
+
+    // Checks that a child with the given ID, name and cardinality can be added.
+    fn check_push<N: Cardinality>(&self, id: &Id, name: &'static str) -> EbmlResult<()> {
+        if N::AT_MOST_ONCE && self.children.iter().any(|node| node.id == *id) {
+            let context = ErrorContext::default().actual(id.clone()).within(&self.path);
+            Err(EbmlError::CardinalityViolation(name, context))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn push_value<T: Element>(&mut self, value: T::Value) -> EbmlResult<()> {
+        self.check_push::<T::Cardinality>(&T::get_id(), T::NAME)?;
+        check_restrictions::<T>(&value).map_err(|err| err.within(&self.path))?;
+        let length = value.get_size().get_value().unwrap_or(0);
+        let mut data = Vec::with_capacity(length as usize);
+        value.encode(&mut data)?;
+        let node = Node::new(T::get_id(), 0, Limits::default(), Validation::default(), data);
+        Arc::make_mut(&mut self.children).push(node);
+        Ok(())
+    }
+
+    fn push_child<NC: Container, NL>(&mut self, child: ContainerImpl<NC, NL>) -> EbmlResult<()> {
+        self.check_push::<NC::Cardinality>(&NC::get_id(), NC::NAME)?;
+        child.check_required()?;
+        Arc::make_mut(&mut self.children).push(Node {
+            id: NC::get_id(),
+            offset: 0,
+            limits: Limits::default(),
+            validation: Validation::default(),
+            data: NodeData::Children(child.children),
+        });
+        Ok(())
+    }
+
+    /// Checks that every child which this container requires is present.
+    pub(crate) fn check_required(&self) -> EbmlResult<()> {
+        check_required::<C, _>(self.children.iter().map(|node| &node.id), &self.path)
+    }
+
+    /// Writes this container and all of its children to a data sink. The sizes of every container
+    /// are computed first, so the container is written with known sizes in one forward pass.
+    ///
+    /// Returns an error without writing anything if a child the container requires is missing.
+    pub fn write<W: Write>(&self, target: &mut W) -> EbmlResult<()> {
+        self.write_with_limits(target, &Limits::default())
+    }
+
+    /// Writes this container like `write`, failing if any of the IDs or sizes are wider than the
+    /// limits allow. Nothing is written if the sizes are too wide.
+    pub(crate) fn write_with_limits<W: Write>(
+        &self,
+        target: &mut W,
+        limits: &Limits,
+    ) -> EbmlResult<()> {
+        self.check_required()?;
+        let mut sizes = Vec::new();
+        let length = compute_sizes(&self.children, &mut sizes)?;
+        let size = Size::from_u64(length).ok_or_else(|| {
+            EbmlError::SizeOverflow(ErrorContext::default().actual(C::get_id()).within(&self.path))
+        })?;
+        let locate = |err: EbmlError| err.within(&self.path);
+        limits.check_id(&C::get_id()).map_err(locate)?;
+        limits.check_size(&size).map_err(|err| err.actual(&C::get_id()).within(&self.path))?;
+        // Check the sizes of the children up front, so that nothing is written if they are too
+        // wide. Which child failed is found when it is written.
+        if sizes.iter().any(|size| limits.check_size(size).is_err()) {
+            let mut sizes = sizes.iter();
+            for child in self.children.iter() {
+                child.write(&mut sizes, &mut io::sink(), limits).map_err(locate)?;
+            }
+        }
+
+        C::get_id().write(target)?;
+        size.write(target)?;
+        let mut sizes = sizes.iter();
+        for child in self.children.iter() {
+            child.write(&mut sizes, target, limits).map_err(locate)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the limits declared by the `EbmlMaxIdWidth` and `EbmlMaxSizeWidth` children of
+    /// this container, if it has any.
+    pub(crate) fn declared_limits(&self) -> EbmlResult<Option<Limits>> {
+        let mut declared = None;
+        for node in self.children.iter() {
+            declared = limits::declare(declared, &node.id, || {
+                node.decode::<UintValue>().map(|value| value.to_repr())
+            }).map_err(|err| err.at(node.offset).within(&self.path))?;
+        }
+        Ok(declared)
+    }
+
+    fn get_zero_or_one_child<NC: Container, NL>(
+        &self,
+    ) -> EbmlResult<Option<ContainerImpl<NC, NL>>> {
+        let mut children = self.get_children::<NC, NL>()?;
+        if children.len() > 1 {
+            Err(self.repeated(NC::get_id(), NC::NAME))
+        } else {
+            Ok(children.pop())
+        }
+    }
+}
+
+impl<C: Container, L> Default for ContainerImpl<C, L> {
+    fn default() -> Self {
+        ContainerImpl::new()
+    }
+}
+
+impl<C, L> ContainerImpl<C, L>
+where
+    L: Add<typenum::P1>,
+    C: Container,
+{
+    /// Adds a value of the given type to the end of this container. Use this method when the
+    /// element is restricted by allowed parent, and not by allowed level.
+    ///
+    /// Returns an error if the element may occur at most once, and is already present.
+    pub fn push_value_by_container<T>(&mut self, value: T::Value) -> EbmlResult<()>
+    where
+        T: Element<MinAllowedLevel = AnyLevel, MaxAllowedLevel = AnyLevel, AllowedParent = C>,
+    {
+        self.push_value::<T>(value)
+    }
+
+    /// Adds a value of the given type to the end of this container. Use this method when the
+    /// element is restricted by allowed level, and not by allowed parent.
+    ///
+    /// Returns an error if the element may occur at most once, and is already present.
+    pub fn push_value_by_level<T>(&mut self, value: T::Value) -> EbmlResult<()>
+    where
+        T: Element<AllowedParent = AnyContainer>,
+        T::MaxAllowedLevel: Exceeds<L>,
+        T::MinAllowedLevel: typenum::IsLessOrEqual<typenum::Sum<L, typenum::P1>, Output = typenum::True>,
+    {
+        self.push_value::<T>(value)
+    }
+
+    /// Adds a child container of the given type to the end of this container. Use this method
+    /// when the child is restricted by allowed parent, and not by allowed level.
+    ///
+    /// Returns an error if the child may occur at most once and is already present, or if a child
+    /// it requires is missing.
+    pub fn push_child_by_container<NC>(
+        &mut self,
+        child: ContainerImpl<NC, typenum::Sum<L, typenum::P1>>,
+    ) -> EbmlResult<()>
+    where
+        NC: Container<MinAllowedLevel = AnyLevel, MaxAllowedLevel = AnyLevel, AllowedParent = C>,
+    {
+        self.push_child(child)
+    }
+
+    /// Adds a child container of the given type to the end of this container. Use this method
+    /// when the child is restricted by allowed level, and not by allowed parent.
+    ///
+    /// Returns an error if the child may occur at most once and is already present, or if a child
+    /// it requires is missing.
+    pub fn push_child_by_level<NC>(
+        &mut self,
+        child: ContainerImpl<NC, typenum::Sum<L, typenum::P1>>,
+    ) -> EbmlResult<()>
+    where
+        NC: Container<AllowedParent = AnyContainer>,
+        NC::MaxAllowedLevel: Exceeds<L>,
+        NC::MinAllowedLevel: typenum::IsLessOrEqual<typenum::Sum<L, typenum::P1>, Output = typenum::True>,
+    {
+        self.push_child(child)
+    }
 }
 
 impl<C, L> ContainerImpl<C, L>
@@ -83,7 +788,7 @@ where
             AllowedParent = C,
         >,
     {
-        unimplemented!()
+        self.get_values::<T>()
     }
 
     /// Finds all values in this container of the given type. Use this method when:
@@ -93,10 +798,10 @@ where
     pub fn get_zero_or_many_values_by_level<T>(&self) -> EbmlResult<Vec<T::Value>>
     where
         T: Element<Cardinality = cardinality::ZeroOrMany, AllowedParent = AnyContainer>,
-        T::MaxAllowedLevel: typenum::IsGreater<L, Output = typenum::True>,
-        T::MinAllowedLevel: typenum::IsLess<L, Output = typenum::True>,
+        T::MaxAllowedLevel: Exceeds<L>,
+        T::MinAllowedLevel: typenum::IsLessOrEqual<typenum::Sum<L, typenum::P1>, Output = typenum::True>,
     {
-        unimplemented!()
+        self.get_values::<T>()
     }
 
     /// Finds the value in this container of the given type. Use this method when:
@@ -112,7 +817,7 @@ where
             AllowedParent = C,
         >,
     {
-        unimplemented!()
+        self.get_zero_or_one_value::<T>()
     }
 
     /// Finds the value in this container of the given type. Use this method when:
@@ -122,16 +827,18 @@ where
     pub fn get_zero_or_one_value_by_level<T>(&self) -> EbmlResult<Option<T::Value>>
     where
         T: Element<Cardinality = cardinality::ZeroOrOne, AllowedParent = AnyContainer>,
-        T::MaxAllowedLevel: typenum::IsGreater<L, Output = typenum::True>,
-        T::MinAllowedLevel: typenum::IsLess<L, Output = typenum::True>,
+        T::MaxAllowedLevel: Exceeds<L>,
+        T::MinAllowedLevel: typenum::IsLessOrEqual<typenum::Sum<L, typenum::P1>, Output = typenum::True>,
     {
-        unimplemented!()
+        self.get_zero_or_one_value::<T>()
     }
 
     /// Finds the value in this container of the given type. Use this method when:
     ///
     /// * The element must occur exactly once in the container.
     /// * The element is restricted by allowed parent, and not by allowed level.
+    ///
+    /// If the element is absent, its default value is returned if it has one.
     pub fn get_exactly_one_value_by_container<T>(&self) -> EbmlResult<T::Value>
     where
         T: Element<
@@ -141,26 +848,30 @@ where
             AllowedParent = C,
         >,
     {
-        unimplemented!()
+        self.get_exactly_one_value::<T>()
     }
 
     /// Finds the value in this container of the given type. Use this method when:
     ///
-    /// * The element may occur zero or one times in the container.
+    /// * The element must occur exactly once in the container.
     /// * The element is restricted by allowed level, and not by allowed parent.
+    ///
+    /// If the element is absent, its default value is returned if it has one.
     pub fn get_exactly_one_value_by_level<T>(&self) -> EbmlResult<T::Value>
     where
         T: Element<Cardinality = cardinality::ExactlyOne, AllowedParent = AnyContainer>,
-        T::MaxAllowedLevel: typenum::IsGreater<L, Output = typenum::True>,
-        T::MinAllowedLevel: typenum::IsLess<L, Output = typenum::True>,
+        T::MaxAllowedLevel: Exceeds<L>,
+        T::MinAllowedLevel: typenum::IsLessOrEqual<typenum::Sum<L, typenum::P1>, Output = typenum::True>,
     {
-        unimplemented!()
+        self.get_exactly_one_value::<T>()
     }
 
     /// Finds the value in this container of the given type. Use this method when:
     ///
     /// * The element must occur once but may occur multiple times in the container.
     /// * The element is restricted by allowed parent, and not by allowed level.
+    ///
+    /// If the element is absent, its default value is returned if it has one.
     pub fn get_one_or_many_values_by_container<T>(&self) -> EbmlResult<(T::Value, Vec<T::Value>)>
     where
         T: Element<
@@ -170,52 +881,663 @@ where
             AllowedParent = C,
         >,
     {
-        unimplemented!()
+        self.get_one_or_many_values::<T>()
     }
 
     /// Finds the value in this container of the given type. Use this method when:
     ///
     /// * The element must occur once but may occur multiple times in the container.
     /// * The element is restricted by allowed level, and not by allowed parent.
+    ///
+    /// If the element is absent, its default value is returned if it has one.
     pub fn get_one_or_many_values_by_level<T>(&self) -> EbmlResult<(T::Value, Vec<T::Value>)>
     where
         T: Element<Cardinality = cardinality::OneOrMany, AllowedParent = AnyContainer>,
-        T::MaxAllowedLevel: typenum::IsGreater<L, Output = typenum::True>,
-        T::MinAllowedLevel: typenum::IsLess<L, Output = typenum::True>,
+        T::MaxAllowedLevel: Exceeds<L>,
+        T::MinAllowedLevel: typenum::IsLessOrEqual<typenum::Sum<L, typenum::P1>, Output = typenum::True>,
+    {
+        self.get_one_or_many_values::<T>()
+    }
+
+    /// Finds the child containers of this container of the given type. Use this method when:
+    ///
+    /// * The child may occur zero or many times in the container.
+    /// * The child is restricted by allowed parent, and not by allowed level.
+    pub fn get_zero_or_many_children_by_container<NC>(
+        &self,
+    ) -> EbmlResult<Vec<ContainerImpl<NC, typenum::Sum<L, typenum::P1>>>>
+    where
+        NC: Container<
+            Cardinality = cardinality::ZeroOrMany,
+            MinAllowedLevel = AnyLevel,
+            MaxAllowedLevel = AnyLevel,
+            AllowedParent = C,
+        >,
+    {
+        self.get_children::<NC, _>()
+    }
+
+    /// Finds the child container of this container of the given type. Use this method when:
+    ///
+    /// * The child may occur zero or one times in the container.
+    /// * The child is restricted by allowed parent, and not by allowed level.
+    pub fn get_zero_or_one_child_by_container<NC>(
+        &self,
+    ) -> EbmlResult<Option<ContainerImpl<NC, typenum::Sum<L, typenum::P1>>>>
+    where
+        NC: Container<
+            Cardinality = cardinality::ZeroOrOne,
+            MinAllowedLevel = AnyLevel,
+            MaxAllowedLevel = AnyLevel,
+            AllowedParent = C,
+        >,
     {
-        unimplemented!()
+        self.get_zero_or_one_child::<NC, _>()
     }
 
     /// Finds the child container of this container of the given type. Use this method when:
     ///
     /// * The child may occur zero or one times in the container.
     /// * The child is restricted by allowed level, and not by allowed parent.
-    pub fn get_zero_or_one_child_by_level<NC: Container>(
+    pub fn get_zero_or_one_child_by_level<NC>(
         &self,
-    ) -> ContainerImpl<NC, typenum::Sum<L, typenum::P1>>
+    ) -> EbmlResult<Option<ContainerImpl<NC, typenum::Sum<L, typenum::P1>>>>
     where
         NC: Container<Cardinality = cardinality::ZeroOrOne, AllowedParent = AnyContainer>,
-        NC::MaxAllowedLevel: typenum::IsGreater<L, Output = typenum::True>,
-        NC::MinAllowedLevel: typenum::IsLess<L, Output = typenum::True>,
+        NC::MaxAllowedLevel: Exceeds<L>,
+        NC::MinAllowedLevel: typenum::IsLessOrEqual<typenum::Sum<L, typenum::P1>, Output = typenum::True>,
     {
-        unimplemented!()
+        self.get_zero_or_one_child::<NC, _>()
     }
 
-    /// Returns the number of children in this container.
-    pub fn len(&self) -> Size {
-        unimplemented!()
+    /// Finds the child container of this container of the given type. Use this method when:
+    ///
+    /// * The child must occur exactly once in the container.
+    /// * The child is restricted by allowed parent, and not by allowed level.
+    pub fn get_exactly_one_child_by_container<NC>(
+        &self,
+    ) -> EbmlResult<ContainerImpl<NC, typenum::Sum<L, typenum::P1>>>
+    where
+        NC: Container<
+            Cardinality = cardinality::ExactlyOne,
+            MinAllowedLevel = AnyLevel,
+            MaxAllowedLevel = AnyLevel,
+            AllowedParent = C,
+        >,
+    {
+        self.get_zero_or_one_child::<NC, _>()?
+            .ok_or_else(|| self.missing(NC::get_id(), NC::NAME))
     }
 
-    /// Returns true if the container has no children.
-    pub fn is_empty(&self) -> bool {
-        self.len().get_value().map(|x| x > 0).unwrap_or(false)
+    /// Finds the child containers of this container of the given type. Use this method when:
+    ///
+    /// * The child must occur once but may occur multiple times in the container.
+    /// * The child is restricted by allowed parent, and not by allowed level.
+    pub fn get_one_or_many_children_by_container<NC>(
+        &self,
+    ) -> EbmlResult<Vec<ContainerImpl<NC, typenum::Sum<L, typenum::P1>>>>
+    where
+        NC: Container<
+            Cardinality = cardinality::OneOrMany,
+            MinAllowedLevel = AnyLevel,
+            MaxAllowedLevel = AnyLevel,
+            AllowedParent = C,
+        >,
+    {
+        let children = self.get_children::<NC, _>()?;
+        if children.is_empty() {
+            Err(self.missing(NC::get_id(), NC::NAME))
+        } else {
+            Ok(children)
+        }
     }
 }
 
-/// Retrieves an EBML root container.
-pub fn root_container() -> ContainerImpl<EbmlHeader, typenum::Z0> {
-    ContainerImpl {
-        _c: PhantomData,
-        _l: PhantomData,
+/// Loads the EBML header, the root container of every document, from a data source. Unlike
+/// `read_document`, this doesn't check the contents of the header.
+pub fn root_container<R: Source>(source: R) -> EbmlResult<ContainerImpl<EbmlHeader, typenum::Z0>> {
+    root_container_with(source, Validation::default())
+}
+
+/// Loads the EBML header like `root_container`, checking its IDs as strictly as `validation`
+/// demands.
+pub fn root_container_with<R: Source>(
+    source: R,
+    validation: Validation,
+) -> EbmlResult<ContainerImpl<EbmlHeader, typenum::Z0>> {
+    read_root(source, validation)?.load()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std_elems::{DocType, DocTypeVersion, EbmlMaxSizeWidth};
+    use {AsciiStringValue, UintValue, Unbounded};
+
+    // Root (0x1A45_0002)
+    //   Flag: uint, exactly one, default 1 (0x4201)
+    //   Tag: uint, zero or many (0x4202)
+    //   Track: container, one or many (0x4203)
+    //     Number: uint, one or many (0x4204)
+    //   Info: container, zero or one, any level from 1 (0x4205)
+    //   Comment: uint, zero or many, any level from 1 up (0x4206)
+
+    #[derive(Debug)]
+    enum Root {}
+    impl Container for Root {
+        type Cardinality = cardinality::ExactlyOne;
+        type ChildOrder = child_order::Insignificant;
+        type AllowedParent = AnyContainer;
+        type MinAllowedLevel = typenum::Z0;
+        type MaxAllowedLevel = typenum::Z0;
+        const NAME: &'static str = "Root";
+
+        fn get_id() -> Id {
+            Id::from_encoded(0x1A45_0002).unwrap()
+        }
+
+        fn child_types() -> Vec<ChildType> {
+            vec![
+                ChildType::element::<Flag>(),
+                ChildType::element::<Tag>(),
+                ChildType::container::<Track>(),
+                ChildType::container::<Info>(),
+            ]
+        }
+    }
+
+    #[derive(Debug)]
+    enum Track {}
+    impl Container for Track {
+        type Cardinality = cardinality::OneOrMany;
+        type ChildOrder = child_order::Insignificant;
+        type AllowedParent = Root;
+        type MinAllowedLevel = AnyLevel;
+        type MaxAllowedLevel = AnyLevel;
+        const NAME: &'static str = "Track";
+
+        fn get_id() -> Id {
+            Id::from_encoded(0x4203).unwrap()
+        }
+
+        fn child_types() -> Vec<ChildType> {
+            vec![ChildType::element::<Number>(), ChildType::container::<Info>()]
+        }
+    }
+
+    #[derive(Debug)]
+    enum Info {}
+    impl Container for Info {
+        type Cardinality = cardinality::ZeroOrOne;
+        type ChildOrder = child_order::Insignificant;
+        type AllowedParent = AnyContainer;
+        type MinAllowedLevel = typenum::P1;
+        type MaxAllowedLevel = typenum::P2;
+        const NAME: &'static str = "Info";
+
+        fn get_id() -> Id {
+            Id::from_encoded(0x4205).unwrap()
+        }
+
+        fn child_types() -> Vec<ChildType> {
+            vec![ChildType::element::<Comment>()]
+        }
+    }
+
+    #[derive(Debug)]
+    enum Flag {}
+    impl Element for Flag {
+        type Value = UintValue;
+        type Cardinality = cardinality::ExactlyOne;
+        type AllowedParent = Root;
+        type MinAllowedLevel = AnyLevel;
+        type MaxAllowedLevel = AnyLevel;
+        const NAME: &'static str = "Flag";
+        const DEFAULT_VALUE: Option<UintValue> = Some(UintValue::Uint1(1));
+
+        fn get_id() -> Id {
+            Id::from_encoded(0x4201).unwrap()
+        }
+    }
+
+    #[derive(Debug)]
+    enum Tag {}
+    impl Element for Tag {
+        type Value = UintValue;
+        type Cardinality = cardinality::ZeroOrMany;
+        type AllowedParent = Root;
+        type MinAllowedLevel = AnyLevel;
+        type MaxAllowedLevel = AnyLevel;
+        const NAME: &'static str = "Tag";
+
+        fn get_id() -> Id {
+            Id::from_encoded(0x4202).unwrap()
+        }
+    }
+
+    #[derive(Debug)]
+    enum Number {}
+    impl Element for Number {
+        type Value = UintValue;
+        type Cardinality = cardinality::OneOrMany;
+        type AllowedParent = Track;
+        type MinAllowedLevel = AnyLevel;
+        type MaxAllowedLevel = AnyLevel;
+        const NAME: &'static str = "Number";
+
+        fn get_id() -> Id {
+            Id::from_encoded(0x4204).unwrap()
+        }
+    }
+
+    #[derive(Debug)]
+    enum Comment {}
+    impl Element for Comment {
+        type Value = UintValue;
+        type Cardinality = cardinality::ZeroOrMany;
+        type AllowedParent = AnyContainer;
+        type MinAllowedLevel = typenum::P1;
+        type MaxAllowedLevel = Unbounded;
+        const NAME: &'static str = "Comment";
+
+        fn get_id() -> Id {
+            Id::from_encoded(0x4206).unwrap()
+        }
+    }
+
+    fn parse(data: &[u8], offset: u64, limits: Limits) -> EbmlResult<Vec<Node>> {
+        parse_nodes(data, offset, limits, &Validation::Strict, &[])
+    }
+
+    fn root(data: &[u8]) -> ContainerImpl<Root, typenum::Z0> {
+        let limits = Limits::default();
+        let nodes = parse_nodes(data, 0, limits, &Validation::Strict, &Root::child_types());
+        ContainerImpl::from_nodes(nodes.unwrap(), vec!["Root"])
+    }
+
+    #[test]
+    fn values() {
+        let root = root(&[
+            0x42, 0x02, 0x81, 0x05,
+            0xEC, 0x81, 0x00, // Void
+            0x42, 0x01, 0x81, 0x00,
+            0x42, 0x02, 0x81, 0x06,
+        ]);
+        assert_eq!(3, root.len().get_value().unwrap());
+        assert!(!root.is_empty());
+
+        assert_eq!(
+            UintValue::Uint1(0),
+            root.get_exactly_one_value_by_container::<Flag>().unwrap()
+        );
+        assert_eq!(
+            vec![UintValue::Uint1(5), UintValue::Uint1(6)],
+            root.get_zero_or_many_values_by_container::<Tag>().unwrap()
+        );
+    }
+
+    #[test]
+    fn default_values() {
+        let root = root(&[]);
+        assert!(root.is_empty());
+        assert_eq!(
+            UintValue::Uint1(1),
+            root.get_exactly_one_value_by_container::<Flag>().unwrap()
+        );
+        assert!(root.get_zero_or_many_values_by_container::<Tag>().unwrap().is_empty());
+    }
+
+    #[test]
+    fn cardinality() {
+        let root = root(&[
+            0x42, 0x01, 0x81, 0x00,
+            0x42, 0x01, 0x81, 0x01,
+        ]);
+        match root.get_exactly_one_value_by_container::<Flag>() {
+            Err(EbmlError::CardinalityViolation("Flag", context)) => {
+                assert_eq!(ErrorContext {
+                    offset: Some(7),
+                    expected: None,
+                    actual: Some(Flag::get_id()),
+                    path: vec!["Root"],
+                }, context);
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+        match root.get_one_or_many_children_by_container::<Track>() {
+            Err(EbmlError::CardinalityViolation("Track", context)) => {
+                assert_eq!(Some(Track::get_id()), context.expected);
+                assert_eq!(vec!["Root"], context.path);
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn children() {
+        let root = root(&[
+            0x42, 0x03, 0x84,
+                0x42, 0x04, 0x81, 0x01,
+            0x42, 0x05, 0x80,
+            0x42, 0x03, 0x88,
+                0x42, 0x04, 0x81, 0x02,
+                0x42, 0x04, 0x81, 0x03,
+        ]);
+
+        let tracks = root.get_one_or_many_children_by_container::<Track>().unwrap();
+        assert_eq!(2, tracks.len());
+        assert_eq!(
+            (UintValue::Uint1(1), vec![]),
+            tracks[0].get_one_or_many_values_by_container::<Number>().unwrap()
+        );
+        assert_eq!(
+            (UintValue::Uint1(2), vec![UintValue::Uint1(3)]),
+            tracks[1].get_one_or_many_values_by_container::<Number>().unwrap()
+        );
+
+        let info = root.get_zero_or_one_child_by_level::<Info>().unwrap().unwrap();
+        assert!(info.is_empty());
+
+        // The children are parsed once, and shared by the containers handed out afterwards.
+        let again = root.get_one_or_many_children_by_container::<Track>().unwrap();
+        assert!(Arc::ptr_eq(&tracks[1].children, &again[1].children));
+        // Changing a container which was handed out leaves the shared children alone.
+        let mut track = again.into_iter().nth(1).unwrap();
+        track.push_child_by_level::<Info>(ContainerImpl::new()).unwrap();
+        assert_eq!(2, tracks[1].children.len());
+        assert_eq!(3, track.children.len());
+    }
+
+    #[test]
+    fn crc32() {
+        let covered = [
+            0x42, 0x01, 0x81, 0x00,
+            0x42, 0x03, 0x84,
+                0x42, 0x04, 0x81, 0x01,
+            0xBF, 0x80, // Misplaced CRC-32
+        ];
+        let mut data = vec![0xBF, 0x84];
+        data.extend_from_slice(&crc32::encode(crc32::checksum(&covered)));
+        data.extend_from_slice(&covered);
+
+        let root = root(&data);
+        assert_eq!(2, root.len().get_value().unwrap());
+        assert_eq!(
+            UintValue::Uint1(0),
+            root.get_exactly_one_value_by_container::<Flag>().unwrap()
+        );
+
+        data[2] ^= 0xFF;
+        match parse(&data, 100, Limits::default()) {
+            Err(EbmlError::ChecksumMismatch(ref context)) if context.offset == Some(100) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+        // The checksum must have four bytes.
+        assert!(parse(&[0xBF, 0x80], 0, Limits::default()).is_err());
+    }
+
+    #[cfg(feature = "legacy-crc32")]
+    #[test]
+    fn legacy_crc32() {
+        let covered = [
+            0x42, 0x01, 0x81, 0x00,
+            0x42, 0x03, 0x84,
+                0x42, 0x04, 0x81, 0x01,
+        ];
+        let mut data = vec![0x42, 0x02, 0x81, 0x05, 0xC3, 0x92, 0x42, 0xFE, 0x84];
+        data.extend_from_slice(&crc32::encode(crc32::checksum(&covered)));
+        data.extend_from_slice(&covered);
+
+        // The elements in the CRC-32 container are children of the root.
+        let root = root(&data);
+        assert_eq!(3, root.len().get_value().unwrap());
+        assert_eq!(
+            UintValue::Uint1(0),
+            root.get_exactly_one_value_by_container::<Flag>().unwrap()
+        );
+        let tracks = root.get_one_or_many_children_by_container::<Track>().unwrap();
+        assert_eq!(
+            (UintValue::Uint1(1), vec![]),
+            tracks[0].get_one_or_many_values_by_container::<Number>().unwrap()
+        );
+
+        let last = data.len() - 1;
+        data[last] = 0x02;
+        match parse(&data, 100, Limits::default()) {
+            Err(EbmlError::ChecksumMismatch(ref context)) if context.offset == Some(106) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        // The checksum must come first.
+        let data = [0xC3, 0x84, 0x42, 0x01, 0x81, 0x00];
+        assert!(parse(&data, 0, Limits::default()).is_err());
+    }
+
+    #[test]
+    fn may_contain() {
+        // Containers accept their listed children, and the elements which may occur anywhere.
+        assert!(Track::may_contain(&Number::get_id()));
+        assert!(Track::may_contain(&Info::get_id()));
+        assert!(Track::may_contain(&Void::get_id()));
+        assert!(Track::may_contain(&Crc32::get_id()));
+        // Their siblings, which end them if they have an unknown size, don't belong to them.
+        assert!(!Track::may_contain(&Tag::get_id()));
+        assert!(!Track::may_contain(&Track::get_id()));
+        assert!(!Track::may_contain(&EbmlHeader::get_id()));
+
+        // The child types are only listed once.
+        static LISTED: AtomicUsize = AtomicUsize::new(0);
+        #[derive(Debug)]
+        enum Counted {}
+        impl Container for Counted {
+            type Cardinality = cardinality::ZeroOrMany;
+            type ChildOrder = child_order::Insignificant;
+            type AllowedParent = Root;
+            type MinAllowedLevel = AnyLevel;
+            type MaxAllowedLevel = AnyLevel;
+            const NAME: &'static str = "Counted";
+
+            fn get_id() -> Id {
+                Id::from_encoded(0x4207).unwrap()
+            }
+
+            fn child_types() -> Vec<ChildType> {
+                LISTED.fetch_add(1, Ordering::SeqCst);
+                vec![ChildType::element::<Tag>()]
+            }
+        }
+        for _ in 0..3 {
+            assert!(Counted::may_contain(&Tag::get_id()));
+            assert!(!Counted::may_contain(&Flag::get_id()));
+        }
+        assert_eq!(1, LISTED.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn malformed_children() {
+        let limits = Limits::default();
+        // The child claims to be bigger than its parent.
+        match parse(&[0x42, 0x03, 0x85, 0x42, 0x04, 0x81, 0x01], 10, limits) {
+            Err(EbmlError::SizeOverflow(context)) => {
+                assert_eq!(Some(10), context.offset);
+                assert_eq!(Some(Track::get_id()), context.actual);
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+        // The size is cut off.
+        match parse(&[0x42, 0x03, 0x40], 10, limits) {
+            Err(EbmlError::UnexpectedEof(context)) => assert_eq!(Some(12), context.offset),
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        let root = root(&[0x42, 0x03, 0x82, 0x42, 0x04]);
+        match root.get_one_or_many_children_by_container::<Track>() {
+            Err(EbmlError::UnexpectedEof(context)) => {
+                assert_eq!(Some(5), context.offset);
+                assert_eq!(vec!["Root", "Track"], context.path);
+            }
+            other => panic!("unexpected result {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn unknown_size_children() {
+        // Containers restricted by level may not have an unknown size.
+        let restricted = root(&[0x42, 0x03, 0x87, 0x42, 0x05, 0xFF, 0x42, 0x06, 0x81, 0x02]);
+        match restricted.get_one_or_many_children_by_container::<Track>() {
+            Err(EbmlError::UnknownSizeNotAllowed(name, context)) => {
+                assert_eq!("Info", name);
+                assert_eq!(Some(3), context.offset);
+            }
+            other => panic!("unexpected result {:?}", other.map(|_| ())),
+        }
+
+        let root = root(&[
+            0x42, 0x03, 0xFF,
+                0x42, 0x04, 0x81, 0x01,
+                0x42, 0x05, 0x84,
+                    0x42, 0x06, 0x81, 0x02,
+            0x42, 0x02, 0x81, 0x05,
+        ]);
+        // The track ends before the first element it may not contain.
+        let tracks = root.get_one_or_many_children_by_container::<Track>().unwrap();
+        assert_eq!(1, tracks.len());
+        assert_eq!(
+            (UintValue::Uint1(1), vec![]),
+            tracks[0].get_one_or_many_values_by_container::<Number>().unwrap()
+        );
+        assert!(tracks[0].get_zero_or_one_child_by_level::<Info>().unwrap().is_some());
+        assert_eq!(
+            vec![UintValue::Uint1(5)],
+            root.get_zero_or_many_values_by_container::<Tag>().unwrap()
+        );
+    }
+
+    #[test]
+    fn limits() {
+        let data = [0x42, 0x03, 0x84, 0x42, 0x04, 0x40, 0x00];
+        let limits = Limits::new(4, 1).unwrap();
+        assert!(parse(&data, 0, Limits::default()).unwrap()[0].children(&[]).is_ok());
+        // The children of a node are parsed with the limits of its document.
+        match parse(&data, 0, limits).unwrap()[0].children(&[]) {
+            Err(EbmlError::SizeOutOfRange(context)) => assert_eq!(Some(5), context.offset),
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        // The root is too big for a 1 byte size.
+        let mut track = ContainerImpl::new();
+        track.push_value_by_container::<Number>(UintValue::Uint1(1)).unwrap();
+        let mut root = ContainerImpl::<Root, typenum::Z0>::new();
+        root.push_child_by_container::<Track>(track).unwrap();
+        for tag in 0..40u8 {
+            root.push_value_by_container::<Tag>(UintValue::Uint1(tag)).unwrap();
+        }
+        let mut written = Vec::new();
+        root.write_with_limits(&mut written, &Limits::new(4, 2).unwrap()).unwrap();
+        assert_eq!(4 + 2 + 7 + 40 * 4, written.len());
+        written.clear();
+        match root.write_with_limits(&mut written, &limits) {
+            Err(EbmlError::SizeOutOfRange(context)) => {
+                assert_eq!(Some(Root::get_id()), context.actual);
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert!(written.is_empty());
+    }
+
+    #[test]
+    fn build() {
+        let mut track = ContainerImpl::new();
+        track.push_value_by_container::<Number>(UintValue::Uint1(3)).unwrap();
+        track.push_child_by_level::<Info>(ContainerImpl::new()).unwrap();
+        let mut root = ContainerImpl::<Root, typenum::Z0>::new();
+        root.push_value_by_container::<Tag>(UintValue::Uint1(7)).unwrap();
+        root.push_child_by_container::<Track>(track).unwrap();
+
+        // Built containers can be queried like loaded ones.
+        let tracks = root.get_one_or_many_children_by_container::<Track>().unwrap();
+        assert_eq!(
+            (UintValue::Uint1(3), vec![]),
+            tracks[0].get_one_or_many_values_by_container::<Number>().unwrap()
+        );
+        assert!(tracks[0].get_zero_or_one_child_by_level::<Info>().unwrap().is_some());
+
+        let mut data = Vec::new();
+        root.write(&mut data).unwrap();
+        assert_eq!(vec![
+            0x1A, 0x45, 0x00, 0x02, 0x8E,
+                0x42, 0x02, 0x81, 0x07,
+                0x42, 0x03, 0x87,
+                    0x42, 0x04, 0x81, 0x03,
+                    0x42, 0x05, 0x80,
+        ], data);
+    }
+
+    #[test]
+    fn required_children() {
+        // Children are required if they must occur and don't have a default value.
+        assert!(ChildType::element::<Number>().required);
+        assert!(ChildType::container::<Track>().required);
+        assert!(!ChildType::element::<Flag>().required);
+        assert!(!ChildType::element::<Tag>().required);
+        assert!(!ChildType::container::<Info>().required);
+        // Containers which don't override `required_children` find them in their child types.
+        assert_eq!(vec![(Track::get_id(), "Track")], Root::required_children());
+        assert_eq!(vec![(Number::get_id(), "Number")], Track::required_children());
+        assert!(Info::required_children().is_empty());
+    }
+
+    #[test]
+    fn build_cardinality() {
+        let mut root = ContainerImpl::<Root, typenum::Z0>::new();
+        root.push_value_by_container::<Flag>(UintValue::Uint1(0)).unwrap();
+        match root.push_value_by_container::<Flag>(UintValue::Uint1(0)) {
+            Err(EbmlError::CardinalityViolation("Flag", _)) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        // Tracks must contain a number.
+        match root.push_child_by_container::<Track>(ContainerImpl::new()) {
+            Err(EbmlError::CardinalityViolation("Number", _)) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+        let mut track = ContainerImpl::<Track, typenum::P1>::new();
+        track.push_value_by_container::<Number>(UintValue::Uint1(1)).unwrap();
+        assert!(track.write(&mut Vec::new()).is_ok());
+        assert!(ContainerImpl::<Track, typenum::P1>::new().write(&mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn unbounded_level() {
+        let mut info = ContainerImpl::<Info, typenum::P1>::new();
+        info.push_value_by_level::<Comment>(UintValue::Uint1(1)).unwrap();
+        assert_eq!(
+            vec![UintValue::Uint1(1)],
+            info.get_zero_or_many_values_by_level::<Comment>().unwrap()
+        );
+
+        let deep = ContainerImpl::<Info, typenum::P1000>::new();
+        assert!(deep.get_zero_or_many_values_by_level::<Comment>().unwrap().is_empty());
+    }
+
+    #[test]
+    fn load_root_container() {
+        let data = include_bytes!("../tests/min_valid_header");
+        let header = root_container(Cursor::new(&data[..])).unwrap();
+
+        assert_eq!(
+            "webm".parse::<AsciiStringValue>().ok(),
+            header.get_zero_or_one_value_by_container::<DocType>().unwrap()
+        );
+        assert_eq!(
+            Some(UintValue::Uint1(8)),
+            header.get_zero_or_one_value_by_container::<EbmlMaxSizeWidth>().unwrap()
+        );
+        assert_eq!(
+            Some(UintValue::Uint1(2)),
+            header.get_zero_or_one_value_by_container::<DocTypeVersion>().unwrap()
+        );
     }
 }
@@ -0,0 +1,102 @@
+//! CRC-32 checksums as used by EBML: the IEEE 802.3 polynomial in reflected form, with the
+//! checksum stored in little-endian byte order.
+
+// The reflected IEEE polynomial.
+const POLYNOMIAL: u32 = 0xEDB8_8320;
+
+// The checksum of every possible byte, for processing data a byte at a time.
+const TABLE: [u32; 256] = make_table();
+
+const fn make_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut value = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            value = if value & 1 == 1 {
+                (value >> 1) ^ POLYNOMIAL
+            } else {
+                value >> 1
+            };
+            bit += 1;
+        }
+        table[i] = value;
+        i += 1;
+    }
+    table
+}
+
+/// A CRC-32 checksum which is computed incrementally.
+#[derive(Debug, Clone)]
+pub(crate) struct Crc32 {
+    state: u32,
+}
+impl Crc32 {
+    pub(crate) fn new() -> Self {
+        Crc32 { state: 0xFFFF_FFFF }
+    }
+
+    /// Adds data to the checksum.
+    pub(crate) fn update(&mut self, data: &[u8]) {
+        for &byte in data {
+            let index = (self.state ^ u32::from(byte)) & 0xFF;
+            self.state = (self.state >> 8) ^ TABLE[index as usize];
+        }
+    }
+
+    /// Returns the checksum of all of the data added so far.
+    pub(crate) fn finish(&self) -> u32 {
+        !self.state
+    }
+}
+
+/// Computes the CRC-32 checksum of the given data.
+pub(crate) fn checksum(data: &[u8]) -> u32 {
+    let mut crc = Crc32::new();
+    crc.update(data);
+    crc.finish()
+}
+
+/// Decodes a checksum from the data of a CRC-32 element.
+pub(crate) fn decode(data: &[u8]) -> Option<u32> {
+    if data.len() == 4 {
+        Some(u32::from(data[0]) | u32::from(data[1]) << 8 | u32::from(data[2]) << 16 |
+            u32::from(data[3]) << 24)
+    } else {
+        None
+    }
+}
+
+/// Encodes a checksum as the data of a CRC-32 element.
+pub(crate) fn encode(checksum: u32) -> [u8; 4] {
+    [checksum as u8, (checksum >> 8) as u8, (checksum >> 16) as u8, (checksum >> 24) as u8]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_values() {
+        assert_eq!(0, checksum(b""));
+        assert_eq!(0xCBF4_3926, checksum(b"123456789"));
+        assert_eq!(0x414F_A339, checksum(b"The quick brown fox jumps over the lazy dog"));
+    }
+
+    #[test]
+    fn incremental() {
+        let mut crc = Crc32::new();
+        crc.update(b"1234");
+        crc.update(b"");
+        crc.update(b"56789");
+        assert_eq!(0xCBF4_3926, crc.finish());
+    }
+
+    #[test]
+    fn coding() {
+        assert_eq!([0x26, 0x39, 0xF4, 0xCB], encode(0xCBF4_3926));
+        assert_eq!(Some(0xCBF4_3926), decode(&[0x26, 0x39, 0xF4, 0xCB]));
+        assert_eq!(None, decode(&[0x26, 0x39, 0xF4]));
+    }
+}
@@ -6,8 +6,9 @@
 
 //! EBML elements, or value with semantic significance.
 
-use {cardinality, EbmlValue, Id, Restriction};
+use {cardinality, EbmlResult, EbmlValue, Id, MaxLevel, Restriction};
 use container::Container;
+use error::{EbmlError, ErrorContext};
 
 use typenum;
 
@@ -27,9 +28,8 @@ pub trait Element {
     /// The maximum possible nesting level of elements of this type. If this type is not restricted
     /// by its parent container, set this to the special type `AnyLevel`.
     ///
-    /// If there should be no maximum, set this to a large value. It is recommended to use
-    /// `typenum::P8192`.
-    type MaxAllowedLevel: typenum::Integer;
+    /// If there should be no maximum, set this to the special type `Unbounded`.
+    type MaxAllowedLevel: MaxLevel;
 
     /// The cardinality of elements of this type.
     ///
@@ -64,6 +64,18 @@ pub trait Element {
     fn get_id() -> Id;
 }
 
+/// Fails if the value doesn't satisfy the restrictions of elements of type `T`. The error records
+/// the ID of the element, but not where it occurred.
+pub(crate) fn check_restrictions<T: Element>(value: &T::Value) -> EbmlResult<()> {
+    match T::get_restrictions() {
+        Some(ref restriction) if !restriction.matches(value) => {
+            let context = ErrorContext::default().actual(T::get_id());
+            Err(EbmlError::RestrictionViolation(T::NAME, context))
+        }
+        _ => Ok(()),
+    }
+}
+
 /// An element containing some data.
 #[derive(Debug)]
 pub struct ElementImpl<E: Element> {
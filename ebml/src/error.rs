@@ -3,30 +3,213 @@ use std::io;
 use std::fmt;
 use std::error::Error;
 
+use id::Id;
+
+/// Where an error occurred in a document, as far as it is known.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ErrorContext {
+    /// The absolute position in the document of the element or data which caused the error. This
+    /// is not known for errors which occur while writing.
+    pub offset: Option<u64>,
+    /// The ID of the element which was expected, if a particular one was.
+    pub expected: Option<Id>,
+    /// The ID of the element which was found, if one was.
+    pub actual: Option<Id>,
+    /// The names of the containers enclosing the error, outermost first. Elements at the top level
+    /// of a document have an empty path.
+    pub path: Vec<&'static str>,
+}
+impl ErrorContext {
+    /// Creates a context for an error at the given absolute position.
+    pub(crate) fn at(offset: u64) -> Self {
+        ErrorContext {
+            offset: Some(offset),
+            ..ErrorContext::default()
+        }
+    }
+
+    /// Sets the ID of the element which was expected.
+    pub(crate) fn expected(mut self, id: Id) -> Self {
+        self.expected = Some(id);
+        self
+    }
+
+    /// Sets the ID of the element which was found.
+    pub(crate) fn actual(mut self, id: Id) -> Self {
+        self.actual = Some(id);
+        self
+    }
+
+    /// Sets the path of the container enclosing the error.
+    pub(crate) fn within(mut self, path: &[&'static str]) -> Self {
+        self.path = path.to_vec();
+        self
+    }
+}
+impl fmt::Display for ErrorContext {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(offset) = self.offset {
+            write!(fmt, " at offset {}", offset)?;
+        }
+        if !self.path.is_empty() {
+            write!(fmt, " in {}", self.path.join("/"))?;
+        }
+        match (&self.expected, &self.actual) {
+            (Some(expected), Some(actual)) => {
+                write!(fmt, " (expected ID {}, found {})", expected, actual)?
+            }
+            (Some(expected), None) => write!(fmt, " (expected ID {})", expected)?,
+            (None, Some(actual)) => write!(fmt, " (ID {})", actual)?,
+            (None, None) => {}
+        }
+        Ok(())
+    }
+}
+
 /// An error which can occur parsing, writing, or manipulating an EBML document.
 #[derive(Debug)]
 pub enum EbmlError {
     /// An error from the standard I/O library.
     StdIo(io::Error),
     /// The EBML document, despite being valid EBML, has malformed content.
-    MalformedDocument,
-    /// An EBML ID was out of range.
-    IdOutOfRange,
+    MalformedDocument(ErrorContext),
+    /// The data ended in the middle of an element.
+    UnexpectedEof(ErrorContext),
+    /// An ID or size was not a valid variable-width integer.
+    InvalidVint(ErrorContext),
+    /// An EBML ID was wider than the document allows.
+    IdOutOfRange(ErrorContext),
+    /// A size was wider than the document allows.
+    SizeOutOfRange(ErrorContext),
+    /// An element extends past the end of the container holding it, or is too big to be encoded.
+    SizeOverflow(ErrorContext),
     /// The wrong ID was read.
-    WrongId,
+    WrongId(ErrorContext),
+    /// The element or container with the given name occurred too many times, or not at all
+    /// although it must occur.
+    CardinalityViolation(&'static str, ErrorContext),
+    /// The data of an element isn't a valid value of its type, such as a string which isn't
+    /// printable ASCII.
+    InvalidValue(ErrorContext),
+    /// The value of the element with the given name didn't satisfy the element's restrictions.
+    RestrictionViolation(&'static str, ErrorContext),
+    /// The container with the given name had an unknown size, although it is restricted by
+    /// nesting level.
+    UnknownSizeNotAllowed(&'static str, ErrorContext),
+    /// The CRC-32 checksum stored in the element at the offset of the context doesn't match the
+    /// data it covers.
+    ChecksumMismatch(ErrorContext),
+    /// The document can't be read by this reader, because the read version element with the given
+    /// name is higher than the reader supports.
+    UnsupportedVersion(&'static str, ErrorContext),
+}
+impl EbmlError {
+    /// Returns where the error occurred, for the kinds of errors which record it.
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match *self {
+            EbmlError::MalformedDocument(ref context) |
+            EbmlError::UnexpectedEof(ref context) |
+            EbmlError::InvalidVint(ref context) |
+            EbmlError::IdOutOfRange(ref context) |
+            EbmlError::SizeOutOfRange(ref context) |
+            EbmlError::SizeOverflow(ref context) |
+            EbmlError::WrongId(ref context) |
+            EbmlError::InvalidValue(ref context) |
+            EbmlError::CardinalityViolation(_, ref context) |
+            EbmlError::RestrictionViolation(_, ref context) |
+            EbmlError::UnknownSizeNotAllowed(_, ref context) |
+            EbmlError::ChecksumMismatch(ref context) |
+            EbmlError::UnsupportedVersion(_, ref context) => Some(context),
+            EbmlError::StdIo(_) => None,
+        }
+    }
+
+    fn context_mut(&mut self) -> Option<&mut ErrorContext> {
+        match *self {
+            EbmlError::MalformedDocument(ref mut context) |
+            EbmlError::UnexpectedEof(ref mut context) |
+            EbmlError::InvalidVint(ref mut context) |
+            EbmlError::IdOutOfRange(ref mut context) |
+            EbmlError::SizeOutOfRange(ref mut context) |
+            EbmlError::SizeOverflow(ref mut context) |
+            EbmlError::WrongId(ref mut context) |
+            EbmlError::InvalidValue(ref mut context) |
+            EbmlError::CardinalityViolation(_, ref mut context) |
+            EbmlError::RestrictionViolation(_, ref mut context) |
+            EbmlError::UnknownSizeNotAllowed(_, ref mut context) |
+            EbmlError::ChecksumMismatch(ref mut context) |
+            EbmlError::UnsupportedVersion(_, ref mut context) => Some(context),
+            EbmlError::StdIo(_) => None,
+        }
+    }
+
+    /// Records the position of an error, unless it already has one. Errors are located as close
+    /// to where they occur as possible, so the first position recorded is the most precise.
+    pub(crate) fn at(mut self, offset: u64) -> Self {
+        if let Some(context) = self.context_mut() {
+            context.offset = context.offset.or(Some(offset));
+        }
+        self
+    }
+
+    /// Records the ID of the element an error occurred in, unless it already has one.
+    pub(crate) fn actual(mut self, id: &Id) -> Self {
+        if let Some(context) = self.context_mut() {
+            if context.actual.is_none() {
+                context.actual = Some(id.clone());
+            }
+        }
+        self
+    }
+
+    /// Records the path of the container an error occurred in, unless it already has one. Errors
+    /// which are passed up from a child container keep the child's path.
+    pub(crate) fn within(mut self, path: &[&'static str]) -> Self {
+        if let Some(context) = self.context_mut() {
+            if context.path.is_empty() {
+                context.path = path.to_vec();
+            }
+        }
+        self
+    }
 }
 impl fmt::Display for EbmlError {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        write!(fmt, "EBML error: {}", self.description())
+        write!(fmt, "EBML error: {}", self.description())?;
+        match *self {
+            EbmlError::CardinalityViolation(name, _) |
+            EbmlError::RestrictionViolation(name, _) |
+            EbmlError::UnknownSizeNotAllowed(name, _) |
+            EbmlError::UnsupportedVersion(name, _) => {
+                write!(fmt, " ({})", name)?;
+            }
+            _ => {}
+        }
+        if let Some(context) = self.context() {
+            write!(fmt, "{}", context)?;
+        }
+        Ok(())
     }
 }
 impl Error for EbmlError {
     fn description(&self) -> &str {
         match *self {
             EbmlError::StdIo(ref e) => e.description(),
-            EbmlError::MalformedDocument => "malformed EBML document",
-            EbmlError::IdOutOfRange => "an id was out of range",
-            EbmlError::WrongId => "the wrong id was read",
+            EbmlError::MalformedDocument(_) => "malformed EBML document",
+            EbmlError::UnexpectedEof(_) => "the data ended in the middle of an element",
+            EbmlError::InvalidVint(_) => "an invalid variable-width integer was read",
+            EbmlError::IdOutOfRange(_) => "an id was out of range",
+            EbmlError::SizeOutOfRange(_) => "a size was out of range",
+            EbmlError::SizeOverflow(_) => "an element was too big for its container",
+            EbmlError::WrongId(_) => "the wrong id was read",
+            EbmlError::InvalidValue(_) => "an element's data was invalid for its type",
+            EbmlError::CardinalityViolation(..) => "an element occurred the wrong number of times",
+            EbmlError::RestrictionViolation(..) => "a value was outside of its element's range",
+            EbmlError::UnknownSizeNotAllowed(..) => {
+                "a container restricted by nesting level had an unknown size"
+            }
+            EbmlError::ChecksumMismatch(_) => "a CRC-32 checksum didn't match",
+            EbmlError::UnsupportedVersion(..) => "the document requires a newer reader",
         }
     }
 
@@ -40,9 +223,46 @@ impl Error for EbmlError {
 }
 impl From<io::Error> for EbmlError {
     fn from(e: io::Error) -> Self {
-        EbmlError::StdIo(e)
+        if e.kind() == io::ErrorKind::UnexpectedEof {
+            EbmlError::UnexpectedEof(ErrorContext::default())
+        } else {
+            EbmlError::StdIo(e)
+        }
     }
 }
 
 /// A `Result` with error type `EbmlError`.
 pub type EbmlResult<T> = Result<T, EbmlError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display() {
+        let context = ErrorContext::at(12)
+            .expected(Id::from_encoded(0x4286).unwrap())
+            .within(&["EBML"]);
+        let err = EbmlError::CardinalityViolation("EBMLVersion", context);
+        assert_eq!(
+            "EBML error: an element occurred the wrong number of times (EBMLVersion) at offset 12 \
+             in EBML (expected ID 0x4286)",
+            err.to_string()
+        );
+
+        let context = ErrorContext::at(5).actual(Id::from_encoded(0xEC).unwrap());
+        let err = EbmlError::SizeOverflow(context).within(&["Segment", "Cluster"]).at(7);
+        assert_eq!(
+            "EBML error: an element was too big for its container at offset 5 in Segment/Cluster \
+             (ID 0xEC)",
+            err.to_string()
+        );
+        let err = EbmlError::MalformedDocument(ErrorContext::default());
+        assert_eq!("EBML error: malformed EBML document", err.to_string());
+
+        let err = EbmlError::SizeOutOfRange(ErrorContext::default())
+            .actual(&Id::from_encoded(0xEC).unwrap())
+            .at(3);
+        assert_eq!("EBML error: a size was out of range at offset 3 (ID 0xEC)", err.to_string());
+    }
+}
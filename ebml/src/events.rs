@@ -0,0 +1,582 @@
+//! A schema-agnostic pull parser for EBML documents. Instead of building a tree or reading typed
+//! containers, the parser produces a flat sequence of events as it encounters the start of a
+//! container, a value, or the end of a container. This makes it possible to process documents
+//! which are too large to hold in memory, without knowing their element types up front.
+//!
+//! ```
+//! use std::io::Cursor;
+//! use ebml::Id;
+//! use ebml::events::{Event, Parser};
+//!
+//! let data = [0x1A, 0x45, 0xDF, 0xA3, 0x84, 0x42, 0x86, 0x81, 0x01];
+//! let header = Id::from_encoded(0x1A45_DFA3).unwrap();
+//! let parser = Parser::new(Cursor::new(&data[..]), |id: &Id| *id == header).unwrap();
+//!
+//! for event in parser {
+//!     match event.unwrap() {
+//!         Event::StartContainer(id, size, offset) => {}
+//!         Event::Value(id, data) => {}
+//!         Event::EndContainer(offset) => {}
+//!     }
+//! }
+//! ```
+
+
+use {EbmlResult, Element, Id, Size};
+use error::{EbmlError, ErrorContext};
+use limits::Validation;
+use peek::PeekableReader;
+use source::Source;
+use std_elems::{Crc32, Void};
+
+/// Tells the parser which elements are containers. Any function from an `&Id` to a `bool` can be
+/// used as a schema, as can a pair of such a function and a function from the IDs of a parent and
+/// a child to a `bool`, which implements `may_contain`.
+pub trait Schema {
+    /// Returns true if elements with the given ID are containers, and false if they are values.
+    fn is_container(&self, id: &Id) -> bool;
+
+    /// Returns true if an element with the ID `child` may occur in a container with the ID
+    /// `parent`. This is used to find the end of containers with unknown sizes, which end at the
+    /// first element which is not a valid child.
+    ///
+    /// By default, only `Void` and `Crc32` elements are valid children, so a container with an
+    /// unknown size ends at its first other child instead of swallowing the elements after it.
+    /// Schemas for documents with such containers should list their children.
+    fn may_contain(&self, _parent: &Id, child: &Id) -> bool {
+        *child == Void::get_id() || *child == Crc32::get_id()
+    }
+}
+impl<F: Fn(&Id) -> bool> Schema for F {
+    fn is_container(&self, id: &Id) -> bool {
+        self(id)
+    }
+}
+impl<F: Fn(&Id) -> bool, G: Fn(&Id, &Id) -> bool> Schema for (F, G) {
+    fn is_container(&self, id: &Id) -> bool {
+        (self.0)(id)
+    }
+
+    fn may_contain(&self, parent: &Id, child: &Id) -> bool {
+        (self.1)(parent, child)
+    }
+}
+
+/// An event produced by the `Parser`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    /// The start of a container, with its ID, the size of its data, and the absolute position of
+    /// the first byte of its ID. Every container is eventually followed by a matching
+    /// `EndContainer` event.
+    StartContainer(Id, Size, u64),
+    /// A value, with its ID and its undecoded data. Use `EbmlValue::decode` to decode it.
+    Value(Id, Vec<u8>),
+    /// The end of the most recently started container, with the absolute position of the first
+    /// byte after it. For containers with an unknown size, this is where the first element which
+    /// is not one of its children starts.
+    EndContainer(u64),
+}
+
+// A container which has been started but not ended.
+#[derive(Debug)]
+struct Open {
+    id: Id,
+    // Whether the size of the container is known.
+    known: bool,
+    // The absolute position of the end of the container if its size is known, or the end of its
+    // parent if not.
+    end: Option<u64>,
+}
+
+/// A pull parser which reads `Event`s from a data source.
+///
+/// The parser implements `Iterator`; it stops after the end of the document, or after the first
+/// error. If the data is truncated, the last item is an `UnexpectedEof` error, and every event
+/// before it is complete.
+#[derive(Debug)]
+pub struct Parser<R: Source, S: Schema> {
+    source: PeekableReader<R>,
+    schema: S,
+    open: Vec<Open>,
+    done: bool,
+}
+impl<R: Source, S: Schema> Parser<R, S> {
+    /// Creates a parser reading from the given source. IDs which RFC 8794 forbids are rejected
+    /// with `InvalidVint`.
+    pub fn new(source: R, schema: S) -> EbmlResult<Self> {
+        Parser::with_validation(source, schema, Validation::default())
+    }
+
+    /// Creates a parser reading from the given source, which checks IDs as strictly as
+    /// `validation` demands.
+    pub fn with_validation(source: R, schema: S, validation: Validation) -> EbmlResult<Self> {
+        let mut source = PeekableReader::new(source)?;
+        source.set_validation(validation);
+        Ok(Parser {
+            source,
+            schema,
+            open: Vec::new(),
+            done: false,
+        })
+    }
+
+    /// The nesting level of the next event; top level elements have level 0.
+    pub fn level(&self) -> usize {
+        self.open.len()
+    }
+
+    /// Skips the rest of the most recently started container. The next event is its
+    /// `EndContainer`.
+    pub fn skip_container(&mut self) -> EbmlResult<()> {
+        let (known, end) = match self.open.last() {
+            Some(open) => (open.known, open.end),
+            None => return Ok(()),
+        };
+
+        if let (true, Some(end)) = (known, end) {
+            let amount = end - self.source.position();
+            self.source.advance(amount as usize)?;
+        } else {
+            while !self.at_end()? {
+                self.skip_element(end)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Skips the next element, which must end before `end` if it is given. If it has an unknown
+    // size, it must be a container, which ends at the first element the schema doesn't allow in
+    // it.
+    fn skip_element(&mut self, end: Option<u64>) -> EbmlResult<()> {
+        let position = self.source.position();
+        let id = Id::load(&mut self.source)?;
+        match Size::load(&mut self.source)?.get_value() {
+            Some(length) => Ok(self.source.advance(length as usize)?),
+            None if self.schema.is_container(&id) => {
+                while end.map(|end| self.source.position() < end).unwrap_or(true) &&
+                        !self.source.peek8().is_empty() &&
+                        self.schema.may_contain(&id, &Id::peek(&self.source)?) {
+                    self.skip_element(end)?;
+                }
+                Ok(())
+            }
+            None => Err(EbmlError::MalformedDocument(ErrorContext::at(position).actual(id))),
+        }
+    }
+
+    // Checks whether the most recently started container has ended.
+    fn at_end(&mut self) -> EbmlResult<bool> {
+        let open = match self.open.last() {
+            Some(open) => open,
+            None => return Ok(false),
+        };
+
+        if let Some(end) = open.end {
+            if self.source.position() >= end {
+                return Ok(true);
+            }
+        }
+        if self.source.peek8().is_empty() {
+            return if open.known {
+                Err(EbmlError::UnexpectedEof(ErrorContext::at(self.source.position())))
+            } else {
+                Ok(true)
+            };
+        }
+        Ok(!open.known && !self.schema.may_contain(&open.id, &Id::peek(&self.source)?))
+    }
+
+    /// Reads the next event, returning `None` at the end of the document.
+    pub fn next_event(&mut self) -> EbmlResult<Option<Event>> {
+        let position = self.source.position();
+        if self.at_end()? {
+            self.open.pop();
+            return Ok(Some(Event::EndContainer(position)));
+        }
+        if self.open.is_empty() && self.source.peek8().is_empty() {
+            return Ok(None);
+        }
+
+        let id = Id::load(&mut self.source)?;
+        let size = Size::load(&mut self.source)?;
+        let start = self.source.position();
+        let parent_end = self.open.last().and_then(|open| open.end);
+        let end = size.get_value().map(|length| start + length);
+        if let (Some(parent_end), Some(end)) = (parent_end, end) {
+            if end > parent_end {
+                return Err(EbmlError::SizeOverflow(ErrorContext::at(position).actual(id)));
+            }
+        }
+
+        if self.schema.is_container(&id) {
+            self.open.push(Open {
+                id: id.clone(),
+                known: end.is_some(),
+                end: end.or(parent_end),
+            });
+            Ok(Some(Event::StartContainer(id, size, position)))
+        } else {
+            let length = size.get_value().ok_or_else(|| {
+                EbmlError::MalformedDocument(ErrorContext::at(position).actual(id.clone()))
+            })?;
+            let data = self
+                .source
+                .read_bytes(length as usize)
+                .map_err(|err| EbmlError::from(err).at(position))?;
+            Ok(Some(Event::Value(id, data)))
+        }
+    }
+}
+impl<R: Source, S: Schema> Iterator for Parser<R, S> {
+    type Item = EbmlResult<Event>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let result = self.next_event();
+        match result {
+            Ok(Some(event)) => Some(Ok(event)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn id(data: u32) -> Id {
+        Id::from_encoded(data).unwrap()
+    }
+
+    fn is_container(id: &Id) -> bool {
+        *id == Id::from_encoded(0x1A45_DFA3).unwrap() || *id == Id::from_encoded(0xA0).unwrap()
+    }
+
+    fn may_contain(parent: &Id, child: &Id) -> bool {
+        match *parent == id(0xA0) {
+            true => *child == id(0x4286) || *child == id(0xEC),
+            false => *child == id(0x4286) || *child == id(0xA0),
+        }
+    }
+
+    fn parse(data: &[u8]) -> Vec<EbmlResult<Event>> {
+        Parser::new(Cursor::new(data), (is_container, may_contain)).unwrap().collect()
+    }
+
+    // A part of the Matroska schema: a Segment (0x1853_8067) contains Clusters (0x1F43_B675) and
+    // Cues (0x1C53_BB6B), a Cluster contains a Timecode (0xE7) and BlockGroups (0xA0), and a
+    // BlockGroup contains Blocks (0xA1).
+    struct Segment;
+    impl Schema for Segment {
+        fn is_container(&self, element: &Id) -> bool {
+            let containers = [0x1853_8067, 0x1F43_B675, 0x1C53_BB6B, 0xA0];
+            containers.iter().any(|&container| *element == id(container))
+        }
+
+        fn may_contain(&self, parent: &Id, child: &Id) -> bool {
+            let children = [
+                (0x1853_8067, 0x1F43_B675),
+                (0x1853_8067, 0x1C53_BB6B),
+                (0x1F43_B675, 0xE7),
+                (0x1F43_B675, 0xA0),
+                (0xA0, 0xA1),
+            ];
+            children.iter().any(|&(p, c)| *parent == id(p) && *child == id(c))
+        }
+    }
+
+    #[test]
+    fn events() {
+        let data = [
+            0x1A, 0x45, 0xDF, 0xA3, 0x8B,
+                0x42, 0x86, 0x81, 0x01,
+                0xA0, 0x83,
+                    0xEC, 0x81, 0x00,
+                0xA0, 0x80,
+            0x42, 0x82, 0x82, b'h', b'i',
+        ];
+        let events: Vec<_> = parse(&data).into_iter().map(|e| e.unwrap()).collect();
+
+        assert_eq!(vec![
+            Event::StartContainer(id(0x1A45_DFA3), 11u8.into(), 0),
+            Event::Value(id(0x4286), vec![0x01]),
+            Event::StartContainer(id(0xA0), 3u8.into(), 9),
+            Event::Value(id(0xEC), vec![0x00]),
+            Event::EndContainer(14),
+            Event::StartContainer(id(0xA0), 0u8.into(), 14),
+            Event::EndContainer(16),
+            Event::EndContainer(16),
+            Event::Value(id(0x4282), b"hi".to_vec()),
+        ], events);
+    }
+
+    #[test]
+    fn unknown_size() {
+        let data = [
+            0xA0, 0xFF,
+                0x42, 0x86, 0x81, 0x01,
+        ];
+        let events: Vec<_> = parse(&data).into_iter().map(|e| e.unwrap()).collect();
+
+        assert_eq!(vec![
+            Event::StartContainer(id(0xA0), ::UNKNOWN_SIZE, 0),
+            Event::Value(id(0x4286), vec![0x01]),
+            Event::EndContainer(6),
+        ], events);
+    }
+
+    #[test]
+    fn unknown_size_siblings() {
+        // Unknown-sized containers end at the next sibling, or at the end of their parent.
+        let data = [
+            0x1A, 0x45, 0xDF, 0xA3, 0x8A,
+                0xA0, 0xFF,
+                    0x42, 0x86, 0x81, 0x01,
+                0xA0, 0xFF,
+                    0xEC, 0x80,
+            0x42, 0x82, 0x80,
+        ];
+        let events: Vec<_> = parse(&data).into_iter().map(|e| e.unwrap()).collect();
+
+        assert_eq!(vec![
+            Event::StartContainer(id(0x1A45_DFA3), 10u8.into(), 0),
+            Event::StartContainer(id(0xA0), ::UNKNOWN_SIZE, 5),
+            Event::Value(id(0x4286), vec![0x01]),
+            Event::EndContainer(11),
+            Event::StartContainer(id(0xA0), ::UNKNOWN_SIZE, 11),
+            Event::Value(id(0xEC), vec![]),
+            Event::EndContainer(15),
+            Event::EndContainer(15),
+            Event::Value(id(0x4282), vec![]),
+        ], events);
+    }
+
+    #[test]
+    fn unknown_size_schema() {
+        struct Matroska;
+        impl Schema for Matroska {
+            fn is_container(&self, id: &Id) -> bool {
+                *id == Id::from_encoded(0xA0).unwrap()
+            }
+
+            fn may_contain(&self, _: &Id, child: &Id) -> bool {
+                *child == Id::from_encoded(0xEC).unwrap()
+            }
+        }
+
+        let data = [
+            0xA0, 0xFF,
+                0xEC, 0x80,
+            0x42, 0x86, 0x81, 0x01,
+        ];
+        let events: Vec<_> = Parser::new(Cursor::new(&data[..]), Matroska)
+            .unwrap()
+            .map(|e| e.unwrap())
+            .collect();
+
+        assert_eq!(vec![
+            Event::StartContainer(id(0xA0), ::UNKNOWN_SIZE, 0),
+            Event::Value(id(0xEC), vec![]),
+            Event::EndContainer(4),
+            Event::Value(id(0x4286), vec![0x01]),
+        ], events);
+    }
+
+    #[test]
+    fn unknown_size_default() {
+        // Without a list of their children, unknown-sized containers end at their first child
+        // which isn't a `Void` or `Crc32` element.
+        let data = [
+            0xA0, 0xFF,
+                0xEC, 0x80,
+                0x42, 0x86, 0x81, 0x01,
+        ];
+        let events: Vec<_> = Parser::new(Cursor::new(&data[..]), is_container)
+            .unwrap()
+            .map(|e| e.unwrap())
+            .collect();
+
+        assert_eq!(vec![
+            Event::StartContainer(id(0xA0), ::UNKNOWN_SIZE, 0),
+            Event::Value(id(0xEC), vec![]),
+            Event::EndContainer(4),
+            Event::Value(id(0x4286), vec![0x01]),
+        ], events);
+    }
+
+    #[test]
+    fn unknown_size_cues() {
+        // A cluster with an unknown size ends at the cues following it.
+        let data = [
+            0x18, 0x53, 0x80, 0x67, 0xFF,
+                0x1F, 0x43, 0xB6, 0x75, 0xFF,
+                    0xE7, 0x81, 0x00,
+                0x1C, 0x53, 0xBB, 0x6B, 0x80,
+        ];
+        let events: Vec<_> = Parser::new(Cursor::new(&data[..]), Segment)
+            .unwrap()
+            .map(|e| e.unwrap())
+            .collect();
+
+        assert_eq!(vec![
+            Event::StartContainer(id(0x1853_8067), ::UNKNOWN_SIZE, 0),
+            Event::StartContainer(id(0x1F43_B675), ::UNKNOWN_SIZE, 5),
+            Event::Value(id(0xE7), vec![0x00]),
+            Event::EndContainer(13),
+            Event::StartContainer(id(0x1C53_BB6B), 0u8.into(), 13),
+            Event::EndContainer(18),
+            Event::EndContainer(18),
+        ], events);
+    }
+
+    #[test]
+    fn skip() {
+        let data = [
+            0xA0, 0x84,
+                0x42, 0x86, 0x81, 0x01,
+            0x42, 0x86, 0x81, 0x02,
+        ];
+        let mut parser = Parser::new(Cursor::new(&data[..]), is_container).unwrap();
+
+        assert_eq!(0, parser.level());
+        assert_eq!(
+            Event::StartContainer(id(0xA0), 4u8.into(), 0),
+            parser.next_event().unwrap().unwrap()
+        );
+        assert_eq!(1, parser.level());
+        parser.skip_container().unwrap();
+        assert_eq!(Event::EndContainer(6), parser.next_event().unwrap().unwrap());
+        assert_eq!(
+            Event::Value(id(0x4286), vec![0x02]),
+            parser.next_event().unwrap().unwrap()
+        );
+        assert!(parser.next_event().unwrap().is_none());
+
+        let data = [
+            0xA0, 0xFF,
+                0x42, 0x86, 0x81, 0x01,
+            0xA0, 0x80,
+        ];
+        let mut parser = Parser::new(Cursor::new(&data[..]), (is_container, may_contain)).unwrap();
+        parser.next_event().unwrap();
+        parser.skip_container().unwrap();
+        assert_eq!(Event::EndContainer(6), parser.next_event().unwrap().unwrap());
+        assert_eq!(
+            Event::StartContainer(id(0xA0), 0u8.into(), 6),
+            parser.next_event().unwrap().unwrap()
+        );
+    }
+
+    #[test]
+    fn skip_nested() {
+        // Containers with an unknown size are skipped along with their children of unknown size.
+        let data = [
+            0x18, 0x53, 0x80, 0x67, 0xFF,
+                0x1F, 0x43, 0xB6, 0x75, 0xFF,
+                    0xE7, 0x81, 0x00,
+                    0xA0, 0xFF,
+                        0xA1, 0x81, 0x01,
+                0x1F, 0x43, 0xB6, 0x75, 0xFF,
+                    0xE7, 0x81, 0x01,
+                0x1C, 0x53, 0xBB, 0x6B, 0x80,
+        ];
+        let mut parser = Parser::new(Cursor::new(&data[..]), Segment).unwrap();
+        parser.next_event().unwrap();
+        parser.next_event().unwrap();
+        parser.skip_container().unwrap();
+        assert_eq!(Event::EndContainer(18), parser.next_event().unwrap().unwrap());
+        assert_eq!(
+            Event::StartContainer(id(0x1F43_B675), ::UNKNOWN_SIZE, 18),
+            parser.next_event().unwrap().unwrap()
+        );
+
+        let mut parser = Parser::new(Cursor::new(&data[..]), Segment).unwrap();
+        parser.next_event().unwrap();
+        parser.skip_container().unwrap();
+        assert_eq!(Event::EndContainer(31), parser.next_event().unwrap().unwrap());
+        assert!(parser.next_event().unwrap().is_none());
+
+        // Values may not have an unknown size.
+        let data = [0xA0, 0xFF, 0xA1, 0xFF];
+        let mut parser = Parser::new(Cursor::new(&data[..]), Segment).unwrap();
+        parser.next_event().unwrap();
+        match parser.skip_container() {
+            Err(EbmlError::MalformedDocument(context)) => {
+                assert_eq!(Some(2), context.offset);
+                assert_eq!(Some(id(0xA1)), context.actual);
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn truncated() {
+        // The container is cut off.
+        let events = parse(&[0xA0, 0x88, 0x42, 0x86, 0x81, 0x01]);
+        assert_eq!(3, events.len());
+        match events[2] {
+            Err(EbmlError::UnexpectedEof(ref context)) => assert_eq!(Some(6), context.offset),
+            ref other => panic!("unexpected result {:?}", other),
+        }
+
+        // The value is cut off.
+        let events = parse(&[0x42, 0x86, 0x84, 0x01]);
+        assert_eq!(1, events.len());
+        match events[0] {
+            Err(EbmlError::UnexpectedEof(ref context)) => assert_eq!(Some(0), context.offset),
+            ref other => panic!("unexpected result {:?}", other),
+        }
+
+        // The container being skipped is cut off.
+        let data = [0xA0, 0x88, 0x42, 0x86, 0x81, 0x01];
+        let mut parser = Parser::new(Cursor::new(&data[..]), is_container).unwrap();
+        parser.next_event().unwrap();
+        match parser.skip_container() {
+            Err(EbmlError::UnexpectedEof(_)) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        // The child is bigger than its parent.
+        let events = parse(&[0xA0, 0x82, 0x42, 0x86, 0x81, 0x01]);
+        assert_eq!(2, events.len());
+        match events[1] {
+            Err(EbmlError::SizeOverflow(ref context)) => assert_eq!(Some(2), context.offset),
+            ref other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn validation() {
+        // 0x40 0x6C is an over-long encoding of the `Void` ID.
+        let data = [0xA0, 0x83, 0x40, 0x6C, 0x80];
+        let events = parse(&data);
+        assert_eq!(2, events.len());
+        match events[1] {
+            Err(EbmlError::InvalidVint(ref context)) => {
+                assert_eq!(Some(2), context.offset);
+                assert_eq!(Id::from_slice(&data[2..4]).ok(), context.actual);
+            }
+            ref other => panic!("unexpected result {:?}", other),
+        }
+
+        let parser = Parser::with_validation(Cursor::new(&data[..]), is_container,
+                                             Validation::lenient(|_| {})).unwrap();
+        let events: Vec<_> = parser.map(|e| e.unwrap()).collect();
+        assert_eq!(vec![
+            Event::StartContainer(id(0xA0), 3u8.into(), 0),
+            Event::Value(id(0xEC), vec![]),
+            Event::EndContainer(5),
+        ], events);
+    }
+}
@@ -0,0 +1,301 @@
+//! 10-byte floats, in the x87 extended precision format with big-endian byte order: a sign bit, a
+//! 15-bit exponent, and a 64-bit significand with an explicit integer bit.
+
+use std::cmp::Ordering;
+
+use F64Conversion;
+
+// The exponent bias of 10-byte floats and of `f64`s.
+const BIAS: i32 = 16_383;
+const F64_BIAS: i32 = 1_023;
+// The highest bit of a significand, the integer bit of a 10-byte float.
+const INTEGER_BIT: u64 = 1 << 63;
+// The fraction bits of an `f64`.
+const F64_FRACTION: u64 = (1 << 52) - 1;
+
+// A float broken down into its parts.
+#[derive(Debug, Clone, Copy)]
+enum Parts {
+    // The payload holds the fraction bits of a 10-byte float, including the quiet bit.
+    Nan { negative: bool, payload: u64 },
+    Infinite { negative: bool },
+    Zero { negative: bool },
+    // The value is `significand * 2^(exponent - 63)`, where the significand's highest bit is set.
+    Finite { negative: bool, exponent: i32, significand: u64 },
+}
+
+// Breaks a 10-byte float down into its parts. Denormals and unnormals (which have a clear integer
+// bit) are normalized, so every encoding of the same number has the same parts.
+fn from_bytes(data: &[u8; 10]) -> Parts {
+    let negative = data[0] & 0x80 != 0;
+    let exponent = i32::from(data[0] & 0x7F) << 8 | i32::from(data[1]);
+    let significand = data[2..].iter().fold(0u64, |acc, x| (acc << 8) | u64::from(*x));
+
+    if exponent == 0x7FFF {
+        let payload = significand & !INTEGER_BIT;
+        if payload == 0 {
+            Parts::Infinite { negative }
+        } else {
+            Parts::Nan { negative, payload }
+        }
+    } else if significand == 0 {
+        Parts::Zero { negative }
+    } else {
+        // Denormals have the same scale as the smallest normal exponent.
+        let shift = significand.leading_zeros();
+        Parts::Finite {
+            negative,
+            exponent: exponent.max(1) - BIAS - shift as i32,
+            significand: significand << shift,
+        }
+    }
+}
+
+// Breaks an `f64` down into its parts.
+fn from_f64_parts(x: f64) -> Parts {
+    let bits = x.to_bits();
+    let negative = bits >> 63 != 0;
+    let exponent = ((bits >> 52) & 0x7FF) as i32;
+    let fraction = bits & F64_FRACTION;
+
+    if exponent == 0x7FF {
+        if fraction == 0 {
+            Parts::Infinite { negative }
+        } else {
+            Parts::Nan { negative, payload: fraction << 11 }
+        }
+    } else if exponent == 0 && fraction == 0 {
+        Parts::Zero { negative }
+    } else if exponent == 0 {
+        let shift = fraction.leading_zeros();
+        Parts::Finite {
+            negative,
+            exponent: 1 - F64_BIAS - (shift as i32 - 11),
+            significand: fraction << shift,
+        }
+    } else {
+        Parts::Finite {
+            negative,
+            exponent: exponent - F64_BIAS,
+            significand: INTEGER_BIT | fraction << 11,
+        }
+    }
+}
+
+/// Encodes an `f64` as a 10-byte float. Every `f64` can be represented exactly, including the
+/// payloads of NaNs.
+pub(crate) fn from_f64(x: f64) -> [u8; 10] {
+    let (negative, exponent, significand) = match from_f64_parts(x) {
+        Parts::Nan { negative, payload } => (negative, 0x7FFF, INTEGER_BIT | payload),
+        Parts::Infinite { negative } => (negative, 0x7FFF, INTEGER_BIT),
+        Parts::Zero { negative } => (negative, 0, 0),
+        // The exponents of `f64`s are always in range.
+        Parts::Finite { negative, exponent, significand } => {
+            (negative, (exponent + BIAS) as u16, significand)
+        }
+    };
+
+    let mut data = [0u8; 10];
+    data[0] = (exponent >> 8) as u8 | if negative { 0x80 } else { 0 };
+    data[1] = exponent as u8;
+    data[2..].copy_from_slice(&significand.to_be_bytes());
+    data
+}
+
+// Shifts the significand right, rounding to the nearest value with ties to even. Returns the
+// result, and whether it was rounded.
+fn round_shift(significand: u64, shift: u32) -> (u64, bool) {
+    if shift == 0 {
+        return (significand, false);
+    }
+    // The significand is less than half of the lowest bit which would be kept.
+    if shift > 64 {
+        return (0, significand != 0);
+    }
+
+    let wide = u128::from(significand);
+    let kept = (wide >> shift) as u64;
+    let dropped = wide & ((1 << shift) - 1);
+    let half = 1 << (shift - 1);
+    let rounded = if dropped > half || (dropped == half && kept & 1 == 1) {
+        kept + 1
+    } else {
+        kept
+    };
+    (rounded, dropped != 0)
+}
+
+/// Converts a 10-byte float to the nearest `f64`, with ties to even.
+pub(crate) fn to_f64(data: &[u8; 10]) -> F64Conversion {
+    let sign = |negative: bool| if negative { 1u64 << 63 } else { 0 };
+
+    match from_bytes(data) {
+        Parts::Nan { negative, payload } => {
+            let mut fraction = payload >> 11;
+            // A NaN whose payload is lost must not turn into infinity.
+            if fraction == 0 {
+                fraction = 1 << 51;
+            }
+            let x = f64::from_bits(sign(negative) | 0x7FF << 52 | fraction);
+            if fraction << 11 == payload {
+                F64Conversion::Exact(x)
+            } else {
+                F64Conversion::Rounded(x)
+            }
+        }
+        Parts::Infinite { negative } => {
+            F64Conversion::Exact(f64::from_bits(sign(negative) | 0x7FF << 52))
+        }
+        Parts::Zero { negative } => F64Conversion::Exact(f64::from_bits(sign(negative))),
+        Parts::Finite { negative, mut exponent, significand } => {
+            let bits = if exponent >= 1 - F64_BIAS {
+                let (mut rounded, inexact) = round_shift(significand, 11);
+                // Rounding up can carry into the next exponent.
+                if rounded == 1 << 53 {
+                    rounded >>= 1;
+                    exponent += 1;
+                }
+                if exponent > F64_BIAS {
+                    let infinity = f64::from_bits(sign(negative) | 0x7FF << 52);
+                    return F64Conversion::Overflow(infinity);
+                }
+                let biased = (exponent + F64_BIAS) as u64;
+                (sign(negative) | biased << 52 | (rounded & F64_FRACTION), inexact)
+            } else {
+                // Subnormals have fewer significant bits the smaller they are. Rounding up to the
+                // smallest normal sets the lowest exponent bit, which is the right encoding.
+                let shift = 11 + (1 - F64_BIAS - exponent) as u32;
+                let (rounded, inexact) = round_shift(significand, shift);
+                (sign(negative) | rounded, inexact)
+            };
+            match bits {
+                (bits, false) => F64Conversion::Exact(f64::from_bits(bits)),
+                (bits, true) => F64Conversion::Rounded(f64::from_bits(bits)),
+            }
+        }
+    }
+}
+
+/// Compares a 10-byte float with an `f64` exactly. Returns `None` if either is a NaN.
+pub(crate) fn compare(data: &[u8; 10], x: f64) -> Option<Ordering> {
+    // Orders the magnitudes of numbers: zero, then finite numbers, then infinity.
+    fn key(parts: Parts) -> Option<(bool, (u8, i32, u64))> {
+        match parts {
+            Parts::Nan { .. } => None,
+            Parts::Zero { .. } => Some((false, (0, 0, 0))),
+            Parts::Finite { negative, exponent, significand } => {
+                Some((negative, (1, exponent, significand)))
+            }
+            Parts::Infinite { negative } => Some((negative, (2, 0, 0))),
+        }
+    }
+
+    let (left_negative, left) = key(from_bytes(data))?;
+    let (right_negative, right) = key(from_f64_parts(x))?;
+    Some(match (left_negative, right_negative) {
+        (false, false) => left.cmp(&right),
+        (true, true) => right.cmp(&left),
+        (true, false) => Ordering::Less,
+        (false, true) => Ordering::Greater,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bytes(sign_exponent: u16, significand: u64) -> [u8; 10] {
+        let mut data = [0u8; 10];
+        data[..2].copy_from_slice(&sign_exponent.to_be_bytes());
+        data[2..].copy_from_slice(&significand.to_be_bytes());
+        data
+    }
+
+    #[test]
+    fn from_f64_values() {
+        assert_eq!(bytes(0x3FFF, INTEGER_BIT), from_f64(1.0));
+        assert_eq!(bytes(0xC000, INTEGER_BIT), from_f64(-2.0));
+        assert_eq!(bytes(0x0000, 0), from_f64(0.0));
+        assert_eq!(bytes(0x8000, 0), from_f64(-0.0));
+        assert_eq!(bytes(0x7FFF, INTEGER_BIT), from_f64(f64::INFINITY));
+        assert_eq!(bytes(0xFFFF, INTEGER_BIT), from_f64(f64::NEG_INFINITY));
+        // The smallest subnormal is normal in the extended format.
+        assert_eq!(bytes(0x3BCD, INTEGER_BIT), from_f64(f64::from_bits(1)));
+    }
+
+    #[test]
+    fn exact_round_trip() {
+        let values = [1.0, -1.5, 0.1, ::std::f64::consts::PI, f64::MAX, f64::MIN_POSITIVE,
+                      f64::from_bits(1), f64::from_bits(F64_FRACTION), -0.0, f64::INFINITY];
+        for &x in &values {
+            match to_f64(&from_f64(x)) {
+                F64Conversion::Exact(y) => assert_eq!(x.to_bits(), y.to_bits()),
+                other => panic!("{} converted to {:?}", x, other),
+            }
+        }
+
+        let nan = f64::from_bits(0x7FF0_0000_0000_0001);
+        match to_f64(&from_f64(nan)) {
+            F64Conversion::Exact(y) => assert_eq!(nan.to_bits(), y.to_bits()),
+            other => panic!("NaN converted to {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rounding() {
+        // 1 + 2^-63 is rounded down to 1.
+        assert_eq!(F64Conversion::Rounded(1.0), to_f64(&bytes(0x3FFF, INTEGER_BIT | 1)));
+        // 1 + 2^-53 is a tie, which is rounded to the even 1.
+        assert_eq!(F64Conversion::Rounded(1.0), to_f64(&bytes(0x3FFF, INTEGER_BIT | 1 << 10)));
+        // 1 + 3 * 2^-53 is a tie, which is rounded to the even 1 + 2^-51.
+        let x = 1.0 + f64::EPSILON * 2.0;
+        assert_eq!(F64Conversion::Rounded(x), to_f64(&bytes(0x3FFF, INTEGER_BIT | 3 << 10)));
+        // Rounding up carries into the exponent.
+        assert_eq!(F64Conversion::Rounded(2.0), to_f64(&bytes(0x3FFF, u64::MAX)));
+        // An unnormal has the same value as the normal number.
+        assert_eq!(F64Conversion::Exact(1.0), to_f64(&bytes(0x4000, INTEGER_BIT >> 1)));
+    }
+
+    #[test]
+    fn out_of_range() {
+        assert_eq!(F64Conversion::Overflow(f64::INFINITY), to_f64(&bytes(0x43FF, INTEGER_BIT)));
+        assert_eq!(F64Conversion::Overflow(f64::NEG_INFINITY), to_f64(&bytes(0xFFFE, u64::MAX)));
+        // The largest value which rounds down to `f64::MAX`.
+        let max = bytes(0x43FE, u64::MAX << 11 | 0x3FF);
+        assert_eq!(F64Conversion::Rounded(f64::MAX), to_f64(&max));
+
+        // Values smaller than half the smallest subnormal are rounded to zero.
+        assert_eq!(F64Conversion::Rounded(0.0), to_f64(&bytes(0x3BCC, INTEGER_BIT)));
+        assert_eq!(F64Conversion::Rounded(-0.0), to_f64(&bytes(0x8001, INTEGER_BIT)));
+        let smallest = f64::from_bits(1);
+        assert_eq!(F64Conversion::Rounded(smallest), to_f64(&bytes(0x3BCC, INTEGER_BIT | 1)));
+        // Denormals of the extended format.
+        assert_eq!(F64Conversion::Rounded(0.0), to_f64(&bytes(0x0000, 1)));
+    }
+
+    #[test]
+    fn nan() {
+        // A NaN whose payload is only in the low bits stays a NaN.
+        match to_f64(&bytes(0x7FFF, INTEGER_BIT | 1)) {
+            F64Conversion::Rounded(x) => assert!(x.is_nan()),
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert_eq!(None, compare(&bytes(0x7FFF, INTEGER_BIT | 1), 1.0));
+        assert_eq!(None, compare(&from_f64(1.0), f64::NAN));
+    }
+
+    #[test]
+    fn comparison() {
+        let one = from_f64(1.0);
+        let above_one = bytes(0x3FFF, INTEGER_BIT | 1);
+        assert_eq!(Some(Ordering::Equal), compare(&one, 1.0));
+        assert_eq!(Some(Ordering::Greater), compare(&above_one, 1.0));
+        assert_eq!(Some(Ordering::Less), compare(&above_one, 1.0 + f64::EPSILON));
+        assert_eq!(Some(Ordering::Less), compare(&one, f64::INFINITY));
+        assert_eq!(Some(Ordering::Greater), compare(&one, -2.0));
+        assert_eq!(Some(Ordering::Less), compare(&from_f64(-2.0), -1.0));
+        assert_eq!(Some(Ordering::Equal), compare(&from_f64(-0.0), 0.0));
+        assert_eq!(Some(Ordering::Greater), compare(&bytes(0x0000, 1), 0.0));
+        assert_eq!(Some(Ordering::Less), compare(&bytes(0x8000, 1), -0.0));
+    }
+}
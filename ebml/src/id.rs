@@ -1,9 +1,11 @@
 
-use std::io::{Read, Write};
+use std::fmt;
+use std::io::Write;
 
-use error::{EbmlError, EbmlResult};
+use error::EbmlResult;
 use peek::PeekableReader;
 use size::Size;
+use source::Source;
 
 /// An EBML ID. These are nearly identical to Sizes, except there are additional reserved values
 /// and different maximum widths.
@@ -12,19 +14,31 @@ pub struct Id {
     data: Size,
 }
 impl Id {
-    /// Attempts to read an `Id` from a data source.
-    pub(crate) fn load<R: Read>(source: &mut PeekableReader<R>) -> EbmlResult<Self> {
-        let size = Size::load(source)?;
-        if size.get_width() <= 4 {
-            Ok(Id { data: size })
-        } else {
-            Err(EbmlError::IdOutOfRange)
-        }
+    /// Attempts to read an `Id` from a data source. Fails if the `Id` is forbidden, unless the
+    /// source is lenient.
+    pub(crate) fn load<R: Source>(source: &mut PeekableReader<R>) -> EbmlResult<Self> {
+        let id = Self::peek(source)?;
+        source.validation().check_id(&id, source.position())?;
+        source.advance(id.get_width())?;
+        Ok(id)
+    }
+
+    /// Attempts to read an `Id` from a data source without consuming it. Fails if the `Id` is wider
+    /// than the source allows.
+    pub(crate) fn peek<R: Source>(source: &PeekableReader<R>) -> EbmlResult<Self> {
+        let id = Self::from_slice(source.peek8()).map_err(|err| err.at(source.position()))?;
+        source.limits().check_id(&id).map_err(|err| err.at(source.position()))?;
+        Ok(id)
+    }
+
+    /// Attempts to read an `Id` of any width from the start of a buffer.
+    pub(crate) fn from_slice(buf: &[u8]) -> EbmlResult<Self> {
+        Ok(Id { data: Size::from_slice(buf)? })
     }
 
-    /// Attempts to write an `Id` to a data sink.
-    pub(crate) fn write<W: Write>(_target: &mut W) -> EbmlResult<()> {
-        unimplemented!("writing not yet supported")
+    /// Writes this `Id` to a data sink. IDs always have the width of their class.
+    pub fn write<W: Write>(&self, target: &mut W) -> EbmlResult<()> {
+        self.data.write(target)
     }
 
     /// Constructs an EBML ID from its encoded representation.
@@ -101,6 +115,27 @@ impl Id {
     pub fn get_width(&self) -> usize {
         self.data.get_width()
     }
+
+    /// Checks that this ID is allowed by RFC 8794: its value bits may not be all ones or all
+    /// zeros, and it must not fit in fewer bytes. IDs constructed with `from_encoded` or the
+    /// `new_class_*` methods always are.
+    pub(crate) fn is_valid(&self) -> bool {
+        let width = self.get_width();
+        let min = if width == 1 { 1 } else { (1u64 << (7 * (width - 1))) - 1 };
+        self.data.get_value().map(|value| value >= min).unwrap_or(false)
+    }
+}
+/// IDs are displayed in their encoded form, in hexadecimal, such as `0x1A45DFA3`.
+impl fmt::Display for Id {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let mut data = Vec::with_capacity(4);
+        self.write(&mut data).expect("writing to a Vec failed");
+        write!(fmt, "0x")?;
+        for byte in data {
+            write!(fmt, "{:02X}", byte)?;
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -149,4 +184,49 @@ mod tests {
         assert!(Id::new_class_d(0x0FFF_FFFF).is_none());
         assert!(Id::new_class_d(0xFFFF_FFFF).is_none());
     }
+
+    #[test]
+    fn write() {
+        let mut buf = Vec::new();
+        Id::new_class_a(0x6C).unwrap().write(&mut buf).unwrap();
+        assert_eq!(vec![0xEC], buf);
+
+        let mut buf = Vec::new();
+        Id::from_encoded(0x4286).unwrap().write(&mut buf).unwrap();
+        assert_eq!(vec![0x42, 0x86], buf);
+
+        let mut buf = Vec::new();
+        Id::from_encoded(0x2A_D7B1).unwrap().write(&mut buf).unwrap();
+        assert_eq!(vec![0x2A, 0xD7, 0xB1], buf);
+
+        let mut buf = Vec::new();
+        Id::new_class_d(0x0A45_DFA3).unwrap().write(&mut buf).unwrap();
+        assert_eq!(vec![0x1A, 0x45, 0xDF, 0xA3], buf);
+    }
+
+    #[test]
+    fn valid() {
+        assert!(Id::from_slice(&[0x81]).unwrap().is_valid());
+        assert!(Id::from_slice(&[0xFE]).unwrap().is_valid());
+        assert!(Id::from_slice(&[0x40, 0x7F]).unwrap().is_valid());
+        assert!(Id::from_slice(&[0x1A, 0x45, 0xDF, 0xA3]).unwrap().is_valid());
+        assert!(Id::from_slice(&[0x08, 0x0F, 0xFF, 0xFF, 0xFF]).unwrap().is_valid());
+
+        // Reserved.
+        assert!(!Id::from_slice(&[0xFF]).unwrap().is_valid());
+        assert!(!Id::from_slice(&[0x1F, 0xFF, 0xFF, 0xFF]).unwrap().is_valid());
+        // All zeros.
+        assert!(!Id::from_slice(&[0x80]).unwrap().is_valid());
+        assert!(!Id::from_slice(&[0x20, 0x00, 0x00]).unwrap().is_valid());
+        // Could be encoded with fewer bytes.
+        assert!(!Id::from_slice(&[0x40, 0x01]).unwrap().is_valid());
+        assert!(!Id::from_slice(&[0x10, 0x00, 0x00, 0xEC]).unwrap().is_valid());
+        assert!(!Id::from_slice(&[0x20, 0x3F, 0xFE]).unwrap().is_valid());
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!("0xEC", Id::new_class_a(0x6C).unwrap().to_string());
+        assert_eq!("0x1A45DFA3", Id::from_encoded(0x1A45_DFA3).unwrap().to_string());
+    }
 }
@@ -22,6 +22,9 @@
 //! Enable the "chrono" cargo feature in order to support conversion between `chrono` dates and
 //! EBML dates.
 //!
+//! Enable the "legacy-crc32" cargo feature in order to read and write the `Crc32Container` from
+//! early drafts of the EBML specification, in addition to the `Crc32` element of RFC 8794.
+//!
 //! ## Errata
 //!
 //! This library attempts to be a complete implementation of the EBML specification. There are a
@@ -29,46 +32,70 @@
 //!
 //! * The EBML specification allows 10-byte (80 bit) floating point numbers to be stored and read.
 //!   Rust lacks support for a hypothetical `f80` type, so when encountering a value of this type,
-//!   this crate treats it as binary data (a `[u8; 10]`).
-//! * The specification allows restricting the nesting level of an element to an interval which is
-//!   unbounded on the right (without a maximum). In order to represent nesting levels at the type
-//!   level, this is not supported. Instead, you should set the maximum allowed level to a large
-//!   number for such elements. It is recommended to use `typenum::P8192` for this purpose.
-//! * The specification forbids elements whose container restrictions are by nesting level from
-//!   having unknown sizes. This crate does not enforce that rule.
-//! * This crate does not enforce the restriction that elements which must occur one or more times
-//!   in a container actually do so when building a container; it is enforced when parsing, as long
-//!   as you actually request it.
+//!   this crate stores it as binary data (a `[u8; 10]`), which can be converted to the nearest
+//!   `f64` with `FloatValueRepr::to_f64`.
 
 #[cfg(feature = "chrono")]
 extern crate chrono;
 extern crate typenum;
 
-//pub mod read;
+pub mod events;
+pub mod read;
 pub mod restrictions;
+pub mod slice;
 pub mod std_elems;
 pub mod std_containers;
 pub mod value;
+pub mod write;
 
 mod container;
+mod crc32;
 mod element;
 mod error;
+mod float10;
 mod id;
+mod limits;
 mod peek;
+mod signed_vint;
 mod size;
+mod source;
 
-pub use container::{Container, ContainerImpl, root_container};
-pub use error::{EbmlError, EbmlResult};
+pub use container::{ChildType, Container, ContainerImpl, root_container, root_container_with};
+pub use error::{EbmlError, EbmlResult, ErrorContext};
 pub use element::{Element, ElementImpl};
 pub use id::Id;
+pub use limits::Validation;
 pub use restrictions::*;
-pub use size::Size;
+pub use signed_vint::SignedVint;
+pub use size::{Size, UNKNOWN_SIZE};
+pub use source::{Seekable, Source};
 pub use value::*;
 
 /// Set an `Element`'s `MinAllowedLevel` and `MaxAllowedLevel` to this type to show that the element
 /// is not restricted by nesting level.
 pub type AnyLevel = typenum::N1;
 
+/// Set an `Element`'s `MaxAllowedLevel` to this type to show that the element may be nested
+/// arbitrarily deeply.
+#[derive(Debug)]
+pub enum Unbounded {}
+
+/// A marker trait for the types which can be used as the maximum nesting level of an element:
+/// `typenum` integers and `Unbounded`.
+pub trait MaxLevel {}
+impl<I: typenum::Integer> MaxLevel for I {}
+impl MaxLevel for Unbounded {}
+
+/// Implemented by maximum nesting levels which are greater than the nesting level `L`. This
+/// extends `typenum::IsGreater` to `Unbounded`, which is greater than every nesting level.
+pub trait Exceeds<L> {}
+impl<I, L> Exceeds<L> for I
+where
+    I: typenum::Integer + typenum::IsGreater<L, Output = typenum::True>,
+{
+}
+impl<L> Exceeds<L> for Unbounded {}
+
 /// Set an `Element`'s `AllowedParent` to this type to show that the element is not restricted by
 /// parent container.
 #[derive(Debug)]
@@ -84,35 +111,83 @@ impl container::Container for AnyContainer {
     fn get_id() -> Id {
         unreachable!("get_id called on dummy container")
     }
+
+    fn child_types() -> Vec<container::ChildType> {
+        Vec::new()
+    }
+}
+
+/// The pseudo-container holding the top-level elements of a document, such as the `EbmlHeader`.
+/// Its nesting level is -1, so that its children have level 0.
+#[derive(Debug)]
+pub enum Document {}
+impl container::Container for Document {
+    type Cardinality = cardinality::ExactlyOne;
+    type ChildOrder = child_order::Significant;
+    type AllowedParent = AnyContainer;
+    type MinAllowedLevel = AnyLevel;
+    type MaxAllowedLevel = AnyLevel;
+    const NAME: &'static str = "do not use";
+
+    fn get_id() -> Id {
+        unreachable!("get_id called on document pseudo-container")
+    }
+
+    fn child_types() -> Vec<container::ChildType> {
+        // Documents may hold any top-level element, so `may_contain` doesn't use this.
+        Vec::new()
+    }
+
+    fn may_contain(id: &Id) -> bool {
+        // The next document in a stream starts with another header.
+        *id != std_containers::EbmlHeader::get_id()
+    }
 }
 
 /// Cardinalities describe the number of containers or elements which can sit in a container.
 pub mod cardinality {
     /// A marker trait for types that define a Cardinality. This should only be implemented for
     /// types defined in this crate.
-    pub trait Cardinality {}
+    pub trait Cardinality {
+        /// True if elements with this cardinality may occur at most once.
+        const AT_MOST_ONCE: bool;
+        /// True if elements with this cardinality must occur at least once.
+        const AT_LEAST_ONCE: bool;
+    }
 
     /// A cardinality indicating that the element may occur any number of times, including zero.
     #[derive(Debug)]
     pub enum ZeroOrMany {}
-    impl Cardinality for ZeroOrMany {}
+    impl Cardinality for ZeroOrMany {
+        const AT_MOST_ONCE: bool = false;
+        const AT_LEAST_ONCE: bool = false;
+    }
 
     /// A cardinality indicating that the element may occur either once or not at all.
     #[derive(Debug)]
     pub enum ZeroOrOne {}
-    impl Cardinality for ZeroOrOne {}
+    impl Cardinality for ZeroOrOne {
+        const AT_MOST_ONCE: bool = true;
+        const AT_LEAST_ONCE: bool = false;
+    }
 
     /// A cardinality indicating that the element must occur exactly once in each scope it is legal
     /// for it to do so.
     #[derive(Debug)]
     pub enum ExactlyOne {}
-    impl Cardinality for ExactlyOne {}
+    impl Cardinality for ExactlyOne {
+        const AT_MOST_ONCE: bool = true;
+        const AT_LEAST_ONCE: bool = true;
+    }
 
     /// A cardinality indicating that the element must occur at least once in each scope it is
     /// legal for it to do so.
     #[derive(Debug)]
     pub enum OneOrMany {}
-    impl Cardinality for OneOrMany {}
+    impl Cardinality for OneOrMany {
+        const AT_MOST_ONCE: bool = false;
+        const AT_LEAST_ONCE: bool = true;
+    }
 }
 
 /// The child order of a container signifies if the order of its elements is significant.
@@ -0,0 +1,184 @@
+//! Limits on the widths of the IDs and sizes in a document, and how strictly the encodings of
+//! IDs are checked.
+
+use std::fmt;
+use std::sync::Arc;
+
+use {EbmlResult, Element, Id, Size};
+use error::{EbmlError, ErrorContext};
+use std_elems::{EbmlMaxIdWidth, EbmlMaxSizeWidth};
+
+/// The maximum widths of the IDs and sizes in the body of a document, as declared by the
+/// `EbmlMaxIdWidth` and `EbmlMaxSizeWidth` elements of its header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Limits {
+    pub(crate) max_id_width: usize,
+    pub(crate) max_size_width: usize,
+}
+impl Limits {
+    /// Creates limits from the values declared in a header, failing if they are out of range.
+    /// IDs may not be limited to fewer than 4 bytes, and neither IDs nor sizes can be wider than 8
+    /// bytes.
+    pub(crate) fn new(max_id_width: u64, max_size_width: u64) -> EbmlResult<Self> {
+        if !(4..=8).contains(&max_id_width) || !(1..=8).contains(&max_size_width) {
+            return Err(EbmlError::MalformedDocument(ErrorContext::default()));
+        }
+        Ok(Limits {
+            max_id_width: max_id_width as usize,
+            max_size_width: max_size_width as usize,
+        })
+    }
+
+    /// Fails if the ID is too wide.
+    pub(crate) fn check_id(&self, id: &Id) -> EbmlResult<()> {
+        if id.get_width() > self.max_id_width {
+            Err(EbmlError::IdOutOfRange(ErrorContext::default().actual(id.clone())))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Fails if the size is too wide. The error doesn't record which element the size belongs to.
+    pub(crate) fn check_size(&self, size: &Size) -> EbmlResult<()> {
+        if size.get_width() > self.max_size_width {
+            Err(EbmlError::SizeOutOfRange(ErrorContext::default()))
+        } else {
+            Ok(())
+        }
+    }
+}
+impl Default for Limits {
+    /// The limits of a document whose header doesn't declare any.
+    fn default() -> Self {
+        Limits {
+            max_id_width: 4,
+            max_size_width: 8,
+        }
+    }
+}
+
+/// How strictly the encodings of the IDs read from a document are checked. RFC 8794 forbids IDs
+/// whose value bits are all ones, which are reserved, and IDs which are encoded with more bytes
+/// than their value needs, including those whose value bits are all zeros.
+///
+/// Sizes whose first byte is zero can't be decoded at all, so they are rejected with
+/// `InvalidVint` in either mode.
+#[derive(Clone, Default)]
+pub enum Validation {
+    /// Reject forbidden IDs with `InvalidVint`. This is the default.
+    #[default]
+    Strict,
+    /// Accept forbidden IDs, passing the `InvalidVint` error which `Strict` would have returned to
+    /// the given function as a warning. This allows reading files written by broken producers.
+    ///
+    /// The function is shared by every reader of the document, and may keep state such as a list
+    /// of the warnings.
+    Lenient(Arc<dyn Fn(&EbmlError) + Send + Sync>),
+}
+impl Validation {
+    /// Accepts forbidden IDs, passing a warning for each of them to the given function.
+    pub fn lenient<F>(warn: F) -> Self
+    where
+        F: Fn(&EbmlError) + Send + Sync + 'static,
+    {
+        Validation::Lenient(Arc::new(warn))
+    }
+
+    /// Checks that an ID which was read at the given absolute position is encoded as RFC 8794
+    /// requires.
+    pub(crate) fn check_id(&self, id: &Id, offset: u64) -> EbmlResult<()> {
+        if id.is_valid() {
+            return Ok(());
+        }
+
+        let err = EbmlError::InvalidVint(ErrorContext::at(offset).actual(id.clone()));
+        match *self {
+            Validation::Strict => Err(err),
+            Validation::Lenient(ref warn) => {
+                warn(&err);
+                Ok(())
+            }
+        }
+    }
+}
+impl fmt::Debug for Validation {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Validation::Strict => write!(fmt, "Strict"),
+            Validation::Lenient(_) => write!(fmt, "Lenient(..)"),
+        }
+    }
+}
+
+/// Updates the limits declared so far by a header with one of its elements. If the element is
+/// `EbmlMaxIdWidth` or `EbmlMaxSizeWidth`, `width` is called to decode its value.
+pub(crate) fn declare<F>(declared: Option<Limits>, id: &Id, width: F) -> EbmlResult<Option<Limits>>
+where
+    F: FnOnce() -> EbmlResult<u64>,
+{
+    let current = declared.unwrap_or_default();
+    let limits = if *id == EbmlMaxIdWidth::get_id() {
+        Limits::new(width()?, current.max_size_width as u64)
+    } else if *id == EbmlMaxSizeWidth::get_id() {
+        Limits::new(current.max_id_width as u64, width()?)
+    } else {
+        return Ok(declared);
+    };
+    limits.map(Some).map_err(|err| err.actual(id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn declared() {
+        assert!(Limits::new(3, 8).is_err());
+        assert!(Limits::new(9, 8).is_err());
+        assert!(Limits::new(4, 0).is_err());
+        assert_eq!(Limits::default(), Limits::new(4, 8).unwrap());
+
+        let limits = Limits::new(4, 2).unwrap();
+        assert!(limits.check_id(&Id::from_encoded(0x1A45_DFA3).unwrap()).is_ok());
+        assert!(limits.check_size(&Size::from_u64(0x3FFE).unwrap()).is_ok());
+        match limits.check_size(&Size::from_u64(0x3FFF).unwrap()) {
+            Err(EbmlError::SizeOutOfRange(_)) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        let declared = declare(None, &EbmlMaxSizeWidth::get_id(), || Ok(2)).unwrap();
+        assert_eq!(Some(limits), declared);
+        let declared = declare(declared, &EbmlMaxIdWidth::get_id(), || Ok(5)).unwrap();
+        assert_eq!(Some(Limits::new(5, 2).unwrap()), declared);
+        let unrelated = Id::from_encoded(0x4286).unwrap();
+        assert_eq!(declared, declare(declared, &unrelated, || panic!("decoded")).unwrap());
+        assert!(declare(None, &EbmlMaxIdWidth::get_id(), || Ok(3)).is_err());
+    }
+
+    #[test]
+    fn validation() {
+        let valid = Id::from_encoded(0x4286).unwrap();
+        assert!(Validation::Strict.check_id(&valid, 0).is_ok());
+
+        let reserved = Id::from_slice(&[0x7F, 0xFF]).unwrap();
+        match Validation::Strict.check_id(&reserved, 12) {
+            Err(EbmlError::InvalidVint(context)) => {
+                assert_eq!(Some(12), context.offset);
+                assert_eq!(Some(reserved.clone()), context.actual);
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        let warnings = Arc::new(Mutex::new(Vec::new()));
+        let validation = {
+            let warnings = warnings.clone();
+            Validation::lenient(move |err| warnings.lock().unwrap().push(err.to_string()))
+        };
+        assert!(validation.check_id(&reserved, 12).is_ok());
+        assert!(validation.clone().check_id(&reserved, 14).is_ok());
+        assert!(validation.check_id(&valid, 0).is_ok());
+        assert_eq!(2, warnings.lock().unwrap().len());
+        assert!(warnings.lock().unwrap()[1].contains("at offset 14"));
+    }
+}
@@ -1,18 +1,70 @@
 
-use std::io::{self, Bytes, Read};
+use std::cmp;
+use std::io;
 
-/// A utility to allow peeking up to 8 bytes into a reader.
+use EbmlResult;
+use crc32::Crc32;
+use error::{EbmlError, ErrorContext};
+use limits::{Limits, Validation};
+use source::{self, Source};
+
+// The most memory `read_bytes` reserves before the data has been read.
+const MAX_RESERVED: usize = 1 << 16;
+
+// How many bytes are taken from the source at once to be peeked at. Taking more than 8 means
+// that the source only has to be refilled every few small elements, rather than for each of them.
+const WINDOW: usize = 32;
+
+fn unexpected_eof() -> io::Error {
+    io::Error::new(io::ErrorKind::UnexpectedEof, "unexpected end of data")
+}
+
+/// A utility to allow peeking up to 8 bytes into a buffered reader.
+///
+/// The reader can also verify CRC-32 checksums of the bytes read past, for any number of nested
+/// ranges at once, and holds the limits on the widths of the IDs and sizes read from it and how
+/// strictly the IDs are checked.
 #[derive(Debug)]
-pub struct PeekableReader<R: Read> {
-    buf: Vec<u8>,
-    source: Bytes<R>,
+pub struct PeekableReader<R: Source> {
+    source: R,
+    // The next bytes of the data, `peeked[start..end]`, which have already been taken from the
+    // source.
+    peeked: [u8; WINDOW],
+    start: usize,
+    end: usize,
+    position: u64,
+    limits: Limits,
+    validation: Validation,
+    // The checksums being computed, with the innermost last.
+    checksums: Vec<Checksum>,
 }
-impl<R: Read> PeekableReader<R> {
-    /// Creates a new `PeekableReader` from any `Read` source.
+
+// A checksum which is being computed, and has to be verified once the data it covers has been
+// read.
+#[derive(Debug)]
+struct Checksum {
+    crc: Crc32,
+    // The absolute position of the element holding the checksum.
+    offset: u64,
+    // The absolute position of the end of the covered data.
+    end: u64,
+    expected: u32,
+}
+impl<R: Source> PeekableReader<R> {
+    /// Creates a new `PeekableReader` from any `Source`.
     pub fn new(source: R) -> io::Result<Self> {
-        let mut source = source.bytes();
-        let buf = source.by_ref().take(8).collect::<Result<Vec<_>, _>>()?;
-        Ok(PeekableReader { buf, source })
+        let mut reader = PeekableReader {
+            source,
+            peeked: [0; WINDOW],
+            start: 0,
+            end: 0,
+            position: 0,
+            limits: Limits::default(),
+            validation: Validation::default(),
+            checksums: Vec::new(),
+        };
+        reader.fill()?;
+        Ok(reader)
     }
 
     /// "Peeks" at the next 8 bytes. Repeated calls return the same values unless `advance` is
@@ -20,33 +72,182 @@ impl<R: Read> PeekableReader<R> {
     ///
     /// Can return fewer than 8 bytes if there are not enough bytes available to be read.
     pub fn peek8(&self) -> &[u8] {
-        self.buf.as_slice()
-    }
-
-    /// Advances the position of the reader by the specified amount. Returns true if we hit EOF.
-    pub fn advance(&mut self, amount: usize) -> io::Result<bool> {
-        if amount < 8 {
-            self.buf = self.buf.split_off(amount);
-            self.buf.append(&mut self.source
-                .by_ref()
-                .take(amount)
-                .collect::<Result<Vec<_>, _>>()?
-            );
+        &self.peeked[self.start..cmp::min(self.end, self.start + 8)]
+    }
+
+    /// The number of bytes the reader has been advanced by since it was created.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// The limits on the widths of the IDs and sizes read from this reader.
+    pub(crate) fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    /// Changes the limits on the widths of the IDs and sizes read from this reader.
+    pub(crate) fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    /// How strictly the IDs read from this reader are checked.
+    pub(crate) fn validation(&self) -> &Validation {
+        &self.validation
+    }
+
+    /// Changes how strictly the IDs read from this reader are checked.
+    pub(crate) fn set_validation(&mut self, validation: Validation) {
+        self.validation = validation;
+    }
+
+    /// Reads the specified number of bytes, advancing the position of the reader past them. Fails
+    /// with `UnexpectedEof` if EOF is reached before all of them could be read, in which case the
+    /// reader is left at EOF.
+    pub fn read_bytes(&mut self, amount: usize) -> io::Result<Vec<u8>> {
+        // The amount comes from the document, which may be truncated or corrupt, so only a bounded
+        // amount of memory is reserved before the data is actually there.
+        let mut data = Vec::with_capacity(cmp::min(amount, MAX_RESERVED));
+        let start = self.start;
+        let from_peeked = self.advance_peeked(amount);
+        data.extend_from_slice(&self.peeked[start..start + from_peeked]);
+
+        let rest = (amount - from_peeked) as u64;
+        if rest > 0 {
+            self.consume_source(rest, |chunk| data.extend_from_slice(chunk))?;
+        }
+        self.fill()?;
+
+        if data.len() < amount {
+            Err(unexpected_eof())
+        } else {
+            Ok(data)
+        }
+    }
+
+    /// Advances the position of the reader by the specified amount. Fails with `UnexpectedEof` if
+    /// EOF is reached first, in which case the reader is left at EOF.
+    ///
+    /// Unless a checksum is being computed, the bytes beyond the peeked ones are skipped with
+    /// `Source::skip`, without being read.
+    pub fn advance(&mut self, amount: usize) -> io::Result<()> {
+        let from_peeked = self.advance_peeked(amount);
+
+        let rest = (amount - from_peeked) as u64;
+        let skipped = if rest == 0 {
+            0
+        } else if self.checksums.is_empty() {
+            let skipped = self.source.skip(rest)?;
+            self.position += skipped;
+            skipped
+        } else {
+            // The skipped bytes have to be seen to be checksummed.
+            self.consume_source(rest, |_| {})?
+        };
+        self.fill()?;
+
+        if skipped < rest {
+            Err(unexpected_eof())
         } else {
-            self.buf = self.source
-                .by_ref()
-                .skip(amount - 8)
-                .take(8)
-                .collect::<Result<Vec<_>, _>>()?;
+            Ok(())
+        }
+    }
+
+    // Advances past as many of the given number of bytes as have been peeked at, returning how
+    // many that is.
+    fn advance_peeked(&mut self, amount: usize) -> usize {
+        let from_peeked = cmp::min(amount, self.end - self.start);
+        let end = self.start + from_peeked;
+        for checksum in &mut self.checksums {
+            checksum.crc.update(&self.peeked[self.start..end]);
+        }
+        self.start = end;
+        self.position += from_peeked as u64;
+        from_peeked
+    }
+
+    // Advances past the given number of bytes from the source, passing them to `f` a buffer at a
+    // time without copying them. Returns the number of bytes consumed, which is less than `amount`
+    // only at EOF.
+    fn consume_source<F: FnMut(&[u8])>(&mut self, amount: u64, mut f: F) -> io::Result<u64> {
+        let mut consumed = 0;
+        while consumed < amount {
+            let checksums = &mut self.checksums;
+            let len = source::with_buf(&mut self.source, |buf| {
+                let len = cmp::min(buf.len() as u64, amount - consumed) as usize;
+                f(&buf[..len]);
+                for checksum in checksums.iter_mut() {
+                    checksum.crc.update(&buf[..len]);
+                }
+                len
+            })?;
+            if len == 0 {
+                break;
+            }
+            self.source.consume(len);
+            self.position += len as u64;
+            consumed += len as u64;
         }
-        Ok(self.buf.len() < 8)
+        Ok(consumed)
+    }
+
+    // Refills the peeked bytes from the buffer of the source once fewer than 8 are left, as far as
+    // the source allows.
+    fn fill(&mut self) -> io::Result<()> {
+        if self.end - self.start >= 8 {
+            return Ok(());
+        }
+
+        self.peeked.copy_within(self.start..self.end, 0);
+        self.end -= self.start;
+        self.start = 0;
+        while self.end < 8 {
+            let peeked = &mut self.peeked[self.end..];
+            let len = source::with_buf(&mut self.source, |buf| {
+                let len = cmp::min(buf.len(), peeked.len());
+                peeked[..len].copy_from_slice(&buf[..len]);
+                len
+            })?;
+            if len == 0 {
+                break;
+            }
+            self.source.consume(len);
+            self.end += len;
+        }
+        Ok(())
+    }
+
+    /// Starts computing a checksum of the bytes from the current position up to `end`, which has
+    /// to match `expected`. `offset` is the position of the element holding the checksum.
+    pub(crate) fn start_checksum(&mut self, offset: u64, end: u64, expected: u32) {
+        self.checksums.push(Checksum {
+            crc: Crc32::new(),
+            offset,
+            end,
+            expected,
+        });
+    }
+
+    /// Verifies the checksums whose data has been read completely.
+    pub(crate) fn verify_checksums(&mut self) -> EbmlResult<()> {
+        while self.checksums.last().map(|checksum| self.position >= checksum.end) == Some(true) {
+            let checksum = self.checksums.pop().expect("no checksum");
+            if self.position > checksum.end {
+                // An element crossed the end of the covered data.
+                return Err(EbmlError::MalformedDocument(ErrorContext::at(checksum.offset)));
+            }
+            if checksum.crc.finish() != checksum.expected {
+                return Err(EbmlError::ChecksumMismatch(ErrorContext::at(checksum.offset)));
+            }
+        }
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::io::Cursor;
+    use std::io::{BufReader, Cursor};
+    use source::Seekable;
 
     #[test]
     fn advancing() {
@@ -55,9 +256,9 @@ mod tests {
         let mut reader = PeekableReader::new(source).unwrap();
 
         assert_eq!([0, 1, 2, 3, 4, 5, 6, 7], reader.peek8());
-        assert!(!reader.advance(1).unwrap());
+        reader.advance(1).unwrap();
         assert_eq!([1, 2, 3, 4, 5, 6, 7, 8], reader.peek8());
-        assert!(!reader.advance(4).unwrap());
+        reader.advance(4).unwrap();
         assert_eq!([5, 6, 7, 8, 9, 10, 11, 12], reader.peek8());
 
         let mut data = vec![0u8; 255];
@@ -67,7 +268,7 @@ mod tests {
         let source = Cursor::new(data);
         let mut reader = PeekableReader::new(source).unwrap();
 
-        assert!(!reader.advance(13).unwrap());
+        reader.advance(13).unwrap();
         assert_eq!([13, 14, 15, 16, 17, 18, 19, 20], reader.peek8());
     }
 
@@ -77,7 +278,95 @@ mod tests {
         let source = Cursor::new(data);
 
         let mut reader = PeekableReader::new(source).unwrap();
-        assert!(reader.advance(100).unwrap());
+        let err = reader.advance(100).unwrap_err();
+        assert_eq!(io::ErrorKind::UnexpectedEof, err.kind());
         assert_eq!(0, reader.peek8().len());
+        assert_eq!(16, reader.position());
+
+        // Fewer than 8 bytes are left to peek at.
+        let mut reader = PeekableReader::new(Cursor::new([0, 1, 2, 3, 4])).unwrap();
+        reader.advance(2).unwrap();
+        assert_eq!([2, 3, 4], reader.peek8());
+        assert!(reader.advance(4).is_err());
+        assert_eq!(0, reader.peek8().len());
+        assert_eq!(5, reader.position());
+
+        // The size of the data to read may be bogus.
+        let mut reader = PeekableReader::new(Cursor::new([0, 1, 2])).unwrap();
+        assert!(reader.read_bytes(usize::MAX).is_err());
+        assert_eq!(3, reader.position());
+    }
+
+    #[test]
+    fn reading() {
+        let data = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
+        let source = Cursor::new(data);
+        let mut reader = PeekableReader::new(source).unwrap();
+
+        assert_eq!(vec![0, 1, 2], reader.read_bytes(3).unwrap());
+        assert_eq!(3, reader.position());
+        assert_eq!([3, 4, 5, 6, 7, 8, 9, 10], reader.peek8());
+
+        assert_eq!(vec![3, 4, 5, 6, 7, 8, 9, 10, 11, 12], reader.read_bytes(10).unwrap());
+        assert_eq!(13, reader.position());
+        assert_eq!([13, 14, 15], reader.peek8());
+
+        assert!(reader.read_bytes(4).is_err());
+        assert_eq!(0, reader.peek8().len());
+    }
+
+    #[test]
+    fn buffered() {
+        // Peeking and checksumming work across the blocks of the source.
+        let data: Vec<u8> = (0..64).collect();
+        let mut reader = PeekableReader::new(BufReader::with_capacity(5, &data[..])).unwrap();
+        assert_eq!(&data[..8], reader.peek8());
+        reader.advance(3).unwrap();
+        reader.start_checksum(0, 40, ::crc32::checksum(&data[3..40]));
+        assert_eq!(&data[3..13], &reader.read_bytes(10).unwrap()[..]);
+        assert_eq!(&data[13..21], reader.peek8());
+        reader.advance(27).unwrap();
+        reader.verify_checksums().unwrap();
+        assert!(reader.checksums.is_empty());
+        assert_eq!(&data[40..48], reader.peek8());
+
+        // Seekable sources are skipped without reading them.
+        let source = Seekable::new(BufReader::with_capacity(5, Cursor::new(&data))).unwrap();
+        let mut reader = PeekableReader::new(source).unwrap();
+        reader.advance(50).unwrap();
+        assert_eq!(50, reader.position());
+        assert_eq!(&data[50..58], reader.peek8());
+        let err = reader.advance(20).unwrap_err();
+        assert_eq!(io::ErrorKind::UnexpectedEof, err.kind());
+        assert_eq!(64, reader.position());
+        assert_eq!(0, reader.peek8().len());
+    }
+
+    #[test]
+    fn checksums() {
+        let source = Cursor::new(&b"0123456789abcdef"[..]);
+        let mut reader = PeekableReader::new(source).unwrap();
+
+        reader.advance(1).unwrap();
+        reader.start_checksum(0, 16, ::crc32::checksum(b"123456789abcdef"));
+        reader.read_bytes(2).unwrap();
+        reader.start_checksum(1, 10, ::crc32::checksum(b"3456789"));
+        reader.advance(7).unwrap();
+        reader.verify_checksums().unwrap();
+        reader.advance(3).unwrap();
+        reader.verify_checksums().unwrap();
+        assert!(!reader.checksums.is_empty());
+        reader.read_bytes(3).unwrap();
+        reader.verify_checksums().unwrap();
+        assert!(reader.checksums.is_empty());
+
+        let source = Cursor::new(&b"0123456789abcdef"[..]);
+        let mut reader = PeekableReader::new(source).unwrap();
+        reader.start_checksum(5, 4, 0);
+        reader.advance(4).unwrap();
+        match reader.verify_checksums() {
+            Err(EbmlError::ChecksumMismatch(ref context)) if context.offset == Some(5) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
     }
 }
@@ -1,18 +1,24 @@
-
 //! Reading EBML documents
 
 use std::borrow::BorrowMut;
-use std::io::Read;
 use std::marker::PhantomData;
 use std::ops::Add;
+use std::rc::Rc;
 
 use typenum;
 
-use {cardinality, child_order, AnyLevel, Container, EbmlResult, Id, Size};
-use element::Element;
-use error::EbmlError;
+use {cardinality, child_order, AnyContainer, AnyLevel, Container, ContainerImpl, Document,
+     EbmlResult, EbmlValue, Exceeds, Id, Size, UintValue, UNKNOWN_SIZE};
+use container::{child_types_of, nested_child, restricted_by_level, ChildType, Node};
+use crc32;
+use element::{check_restrictions, Element};
+use error::{EbmlError, ErrorContext};
+use limits::Validation;
 use peek::PeekableReader;
-use std_containers::EbmlHeader;
+use source::Source;
+use std_containers::{Crc32Container, EbmlHeader};
+use std_elems::{Crc32, Crc32Value, DocType, DocTypeReadVersion, DocTypeVersion, EbmlMaxIdWidth,
+                EbmlMaxSizeWidth, EbmlReadVersion, EbmlVersion, Void};
 
 // TODO: Don't actually do this; generate a concrete type for each container and value using the
 // macro.
@@ -22,27 +28,539 @@ use std_containers::EbmlHeader;
 /// L: the depth of the current container (using typenum).
 /// R: the type of underlying reader.
 /// B: the type of the actual reader.
+///
+/// Elements are read in the order they occur in the document. Methods which look for an element
+/// skip any other elements preceding it, whether it must be present or not, so an element which
+/// may be absent and doesn't occur takes the rest of the container with it. Once an element has
+/// been found, further occurrences of it are only read if they follow it directly, so that the
+/// elements after them are left for later calls. `Void` elements are always skipped.
+///
+/// If the first child of a container with a known size is a `Crc32` element, the rest of the
+/// container is checked against it once all of it has been read or skipped. `Crc32` elements
+/// anywhere else are skipped. With the `legacy-crc32` feature enabled, the elements in a
+/// `Crc32Container` are read as if they were children of the container itself, and their checksum
+/// is verified in the same way.
+///
+/// A container with an unknown size ends at the end of its parent, at the end of the data, or at
+/// the first element which is not a valid child according to `Container::may_contain`, whichever
+/// comes first. Containers which are restricted by nesting level may not have an unknown size.
+///
+/// If the data ends in the middle of an element, or before the end of a container with a known
+/// size, reading fails with `UnexpectedEof`. Everything read before is unaffected, and
+/// `load_truncated` recovers the complete children of a container which is cut off.
 #[derive(Debug)]
-pub struct ContainerReader<C: Container, L, R: Read, B: BorrowMut<PeekableReader<R>>> {
+pub struct ContainerReader<C: Container, L, R: Source, B: BorrowMut<PeekableReader<R>>> {
     _c: PhantomData<C>,
     _l: PhantomData<L>,
     _r: PhantomData<R>,
 
     source: B,
+    // The names of this container and the containers enclosing it, outermost first.
+    path: Vec<&'static str>,
     length: Size,
+    // The absolute position of the end of the container if its size is known, or the end of its
+    // parent if not.
+    end: Option<u64>,
+    // Where the last child container handed out ends. The child may not have been read to its
+    // end, so we have to skip to there before reading anything else.
+    resume: Option<Resume>,
+}
+
+// The end of a child container.
+#[derive(Debug)]
+enum Resume {
+    // The child has a known size, and ends at this absolute position.
+    Known(u64),
+    // The child has an unknown size, so it ends at the first element which is not a valid child,
+    // or at the end of its parent.
+    Unknown {
+        may_contain: fn(&Id) -> bool,
+        child_types: fn() -> Rc<Vec<ChildType>>,
+        end: Option<u64>,
+    },
+}
+
+// Checks if an element is a `Crc32Container` which should be read transparently.
+pub(crate) fn is_crc32_container(id: &Id) -> bool {
+    cfg!(feature = "legacy-crc32") && *id == Crc32Container::get_id()
+}
+
+// Reads an element holding a CRC-32 checksum, which must have the given ID and end before `end`.
+fn read_checksum<R: Source>(source: &mut PeekableReader<R>, id: &Id, end: u64) -> EbmlResult<u32> {
+    let offset = source.position();
+    let actual = Id::peek(source)?;
+    if actual != *id {
+        let context = ErrorContext::at(offset).expected(id.clone()).actual(actual);
+        return Err(EbmlError::WrongId(context));
+    }
+    let (_, _, data) = read_element(source, Some(end), &[])?;
+    crc32::decode(&data).ok_or_else(|| {
+        EbmlError::MalformedDocument(ErrorContext::at(offset).actual(id.clone()))
+    })
 }
+
+// Reads the next element, which must end before `end` if it is given. Returns its ID, the
+// absolute position of its data, and its data. If it has an unknown size, it must be a container
+// listed in `siblings`, the child types of its parent, and its data is the encoded children up to
+// the first element which isn't one of them.
+fn read_element<R: Source>(
+    source: &mut PeekableReader<R>,
+    end: Option<u64>,
+    siblings: &[ChildType],
+) -> EbmlResult<(Id, u64, Vec<u8>)> {
+    let offset = source.position();
+    let id = Id::load(source)?;
+    let size = Size::load(source)?;
+    let data_offset = source.position();
+    let data = match size.get_value() {
+        Some(length) => {
+            if end.map(|end| data_offset + length > end).unwrap_or(false) {
+                return Err(EbmlError::SizeOverflow(ErrorContext::at(offset).actual(id)));
+            }
+            source
+                .read_bytes(length as usize)
+                .map_err(|err| EbmlError::from(err).at(offset))?
+        }
+        None => {
+            let mut data = Vec::new();
+            pass_children(source, &id, offset, siblings, end, Some(&mut data))?;
+            data
+        }
+    };
+    Ok((id, data_offset, data))
+}
+
+// Skips the next element. If it has an unknown size, it must be a container listed in `siblings`,
+// the child types of its parent, and it ends at the first element which isn't its child, at `end`
+// or at the end of the data.
+fn skip<R: Source>(
+    source: &mut PeekableReader<R>,
+    siblings: &[ChildType],
+    end: Option<u64>,
+) -> EbmlResult<()> {
+    let offset = source.position();
+    let id = Id::load(source)?;
+    match Size::load(source)?.get_value() {
+        Some(length) => Ok(source.advance(length as usize)?),
+        None => pass_children(source, &id, offset, siblings, end, None),
+    }
+}
+
+// Skips the children of a container with an unknown size, whose header has been read, or copies
+// them to `data` if it is given. The container has the given ID and offset, and must be listed in
+// `siblings`.
+fn pass_children<R: Source>(
+    source: &mut PeekableReader<R>,
+    id: &Id,
+    offset: u64,
+    siblings: &[ChildType],
+    end: Option<u64>,
+    mut data: Option<&mut Vec<u8>>,
+) -> EbmlResult<()> {
+    let nested = nested_child(siblings, id, offset)?;
+    let child_types = (nested.child_types)();
+    while end.map(|end| source.position() < end).unwrap_or(true) &&
+            !source.peek8().is_empty() && {
+                let id = Id::peek(source)?;
+                (nested.may_contain)(&id) || is_crc32_container(&id)
+            } {
+        let data = match data {
+            Some(ref mut data) => &mut **data,
+            None => {
+                skip(source, &child_types, end)?;
+                continue;
+            }
+        };
+        let child_offset = source.position();
+        let child_id = Id::load(source)?;
+        let size = Size::load(source)?;
+        child_id.write(data)?;
+        size.write(data)?;
+        match size.get_value() {
+            Some(length) => data.extend_from_slice(&source.read_bytes(length as usize)?),
+            None => pass_children(source, &child_id, child_offset, &child_types, end, Some(data))?,
+        }
+    }
+    Ok(())
+}
+
+impl<C, L, R, B> ContainerReader<C, L, R, B>
+where
+    C: Container,
+    R: Source,
+    B: BorrowMut<PeekableReader<R>>,
+{
+    fn new(mut source: B, parent_end: Option<u64>, path: Vec<&'static str>) -> EbmlResult<Self> {
+        let length = Size::load(source.borrow_mut())?;
+        ContainerReader::with_size(source, length, parent_end, path)
+    }
+
+    // Creates a reader for a container with the given path, whose size has already been read.
+    fn with_size(
+        mut source: B,
+        length: Size,
+        parent_end: Option<u64>,
+        path: Vec<&'static str>,
+    ) -> EbmlResult<Self> {
+        if length.get_value().is_none() && restricted_by_level::<C>() {
+            let context = ErrorContext::default().actual(C::get_id());
+            return Err(EbmlError::UnknownSizeNotAllowed(C::NAME, context));
+        }
+        let end = match length.get_value() {
+            Some(length) => Some(source.borrow_mut().position() + length),
+            None => parent_end,
+        };
+
+        let mut reader = ContainerReader {
+            _c: PhantomData,
+            _l: PhantomData,
+            _r: PhantomData,
+
+            source,
+            path,
+            length,
+            end,
+            resume: None,
+        };
+        let result = reader.read_crc32();
+        reader.locate(result)?;
+        Ok(reader)
+    }
+
+    // Records the current position and the path of this container in the error of `result`,
+    // unless the error already has them.
+    fn locate<T>(&mut self, result: EbmlResult<T>) -> EbmlResult<T> {
+        result.map_err(|err| {
+            let position = self.source.borrow_mut().position();
+            err.at(position).within(&self.path)
+        })
+    }
+
+    // The context of an error at the current position in this container.
+    fn context(&mut self) -> ErrorContext {
+        ErrorContext::at(self.source.borrow_mut().position()).within(&self.path)
+    }
+
+    /// Returns the size of this container's data in bytes. This may be the unknown size.
+    pub fn get_size(&self) -> Size {
+        self.length.clone()
+    }
+
+    /// Returns true if the container has no children.
+    pub fn is_empty(&self) -> bool {
+        self.length.get_value().map(|x| x == 0).unwrap_or(false)
+    }
+
+    /// Returns the ID of the next element in this container, or `None` if there are no more.
+    pub fn next_id(&mut self) -> EbmlResult<Option<Id>> {
+        let result = self.skip_to_next();
+        self.locate(result)
+    }
+
+    // Skips to the next element in this container which isn't a `Crc32` element, and returns its
+    // ID.
+    fn skip_to_next(&mut self) -> EbmlResult<Option<Id>> {
+        let crc32 = Crc32::get_id();
+        while !self.at_end()? {
+            let id = Id::peek(self.source.borrow_mut())?;
+            if id == crc32 {
+                // Only the first child of a container can hold its checksum.
+                skip(self.source.borrow_mut(), &[], self.end)?;
+            } else if is_crc32_container(&id) {
+                self.enter_crc32_container()?;
+            } else {
+                return Ok(Some(id));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Skips the next element in this container, whatever its type. Returns false if there was no
+    /// element to skip.
+    pub fn skip_element(&mut self) -> EbmlResult<bool> {
+        if self.next_id()?.is_none() {
+            return Ok(false);
+        }
+
+        let result = skip(self.source.borrow_mut(), &child_types_of::<C>(), self.end);
+        self.locate(result)?;
+        Ok(true)
+    }
+
+    /// Skips the rest of this container, returning the absolute position of its end. For a
+    /// container with an unknown size, this is where the first element which is not one of its
+    /// children starts.
+    ///
+    /// The elements are skipped one at a time, so that checksums of the skipped data are verified.
+    pub fn finish(mut self) -> EbmlResult<u64> {
+        while self.skip_element()? {}
+        Ok(self.source.borrow_mut().position())
+    }
+
+    /// Reads the rest of this container into memory. Containers whose children may occur in any
+    /// order can only be queried once they are loaded.
+    pub fn load(mut self) -> EbmlResult<ContainerImpl<C, L>> {
+        let mut children = Vec::new();
+        self.load_into(&mut children)?;
+        Ok(ContainerImpl::from_nodes(children, self.path))
+    }
+
+    /// Reads the rest of this container into memory like `load`, but if the data ends in the
+    /// middle of the container, returns the children which were read completely along with the
+    /// `UnexpectedEof` error, instead of failing. This recovers what is left of a truncated
+    /// document, such as an interrupted recording.
+    ///
+    /// The child which is cut off is left out, and checksums covering the missing data can't be
+    /// verified.
+    pub fn load_truncated(mut self) -> EbmlResult<(ContainerImpl<C, L>, Option<EbmlError>)> {
+        let mut children = Vec::new();
+        let truncated = match self.load_into(&mut children) {
+            Ok(()) => None,
+            Err(err @ EbmlError::UnexpectedEof(_)) => Some(err),
+            Err(err) => return Err(err),
+        };
+        Ok((ContainerImpl::from_nodes(children, self.path), truncated))
+    }
+
+    // Reads the rest of this container into `children`.
+    fn load_into(&mut self, children: &mut Vec<Node>) -> EbmlResult<()> {
+        let void = Void::get_id();
+        let child_types = child_types_of::<C>();
+        while self.next_id()?.is_some() {
+            let result = read_element(self.source.borrow_mut(), self.end, &child_types);
+            let (id, offset, data) = self.locate(result)?;
+            if id != void {
+                let source = self.source.borrow_mut();
+                let validation = source.validation().clone();
+                children.push(Node::new(id, offset, source.limits(), validation, data));
+            }
+        }
+        Ok(())
+    }
+
+    // Skips to the end of the last child handed out, and checks if there is any data left in
+    // this container.
+    fn at_end(&mut self) -> EbmlResult<bool> {
+        let source = self.source.borrow_mut();
+        match self.resume.take() {
+            Some(Resume::Known(resume)) if resume > source.position() => {
+                let amount = resume - source.position();
+                source.advance(amount as usize)?;
+            }
+            Some(Resume::Unknown { may_contain, child_types, end }) => {
+                let child_types = child_types();
+                while end.map(|end| source.position() < end).unwrap_or(true) &&
+                        !source.peek8().is_empty() && {
+                            let id = Id::peek(source)?;
+                            may_contain(&id) || is_crc32_container(&id)
+                        } {
+                    skip(source, &child_types, end)?;
+                }
+            }
+            _ => {}
+        }
+
+        source.verify_checksums()?;
+        if let Some(end) = self.end {
+            if source.position() >= end {
+                return Ok(true);
+            }
+        }
+        if self.length.get_value().is_none() {
+            // A container with an unknown size also ends at the end of the data, or at the first
+            // element which can't be its child.
+            if source.peek8().is_empty() {
+                return Ok(true);
+            }
+            let id = Id::peek(source)?;
+            Ok(!C::may_contain(&id) && !is_crc32_container(&id))
+        } else {
+            Ok(false)
+        }
+    }
+
+    // Reads the `Crc32` element if it is the first child of this container, and starts computing
+    // the checksum of the rest of the container.
+    fn read_crc32(&mut self) -> EbmlResult<()> {
+        let end = match (self.length.get_value(), self.end) {
+            (Some(_), Some(end)) => end,
+            _ => return Ok(()),
+        };
+        let source = self.source.borrow_mut();
+        if source.position() >= end || Id::peek(source)? != Crc32::get_id() {
+            return Ok(());
+        }
+
+        let offset = source.position();
+        let expected = read_checksum(source, &Crc32::get_id(), end)?;
+        source.start_checksum(offset, end, expected);
+        Ok(())
+    }
+
+    // Reads the header of the next element, which must be a `Crc32Container`, and its
+    // `Crc32Value`, and starts computing the checksum of the rest of its data.
+    fn enter_crc32_container(&mut self) -> EbmlResult<()> {
+        let source = self.source.borrow_mut();
+        let container_offset = source.position();
+        let id = Id::load(source)?;
+        let length = match Size::load(source)?.get_value() {
+            Some(length) => length,
+            None => {
+                let context = ErrorContext::at(container_offset).actual(id).within(&self.path);
+                return Err(EbmlError::MalformedDocument(context));
+            }
+        };
+        let end = source.position() + length;
+        if self.end.map(|parent_end| end > parent_end).unwrap_or(false) {
+            return Err(EbmlError::SizeOverflow(ErrorContext::at(container_offset).actual(id)));
+        }
+
+        let offset = source.position();
+        let expected = read_checksum(source, &Crc32Value::get_id(), end)?;
+        source.start_checksum(offset, end, expected);
+        Ok(())
+    }
+
+    // Checks if the next element has the given ID, skipping `Void` elements. If `past_others` is
+    // set, skips all other elements preceding it as well.
+    fn find(&mut self, id: &Id, past_others: bool) -> EbmlResult<bool> {
+        let void = Void::get_id();
+        while let Some(next) = self.next_id()? {
+            if next == *id {
+                return Ok(true);
+            } else if past_others || next == void {
+                self.skip_element()?;
+            } else {
+                return Ok(false);
+            }
+        }
+        Ok(false)
+    }
+
+    // Reads the value of the next element, which must have already been found.
+    fn read_value<T: Element>(&mut self) -> EbmlResult<T::Value> {
+        let offset = self.source.borrow_mut().position();
+        let result = read_element(self.source.borrow_mut(), self.end, &[]).and_then(|(_, _, data)| {
+            let value = T::Value::decode(&data).map_err(|err| err.at(offset).actual(&T::get_id()))?;
+            check_restrictions::<T>(&value).map_err(|err| err.at(offset))?;
+            Ok(value)
+        });
+        self.locate(result)
+    }
+
+    // An error for an element of type `T` which occurs again, although it may occur at most once.
+    fn repeated<T: Element>(&mut self) -> EbmlError {
+        EbmlError::CardinalityViolation(T::NAME, self.context().actual(T::get_id()))
+    }
+
+    // An error for an element or container which must occur, but is missing.
+    fn missing(&mut self, id: Id, name: &'static str) -> EbmlError {
+        EbmlError::CardinalityViolation(name, self.context().expected(id))
+    }
+
+    fn read_zero_or_one_value<T: Element>(&mut self) -> EbmlResult<Option<T::Value>> {
+        if self.find(&T::get_id(), true)? {
+            let value = self.read_value::<T>()?;
+            if self.find(&T::get_id(), false)? {
+                return Err(self.repeated::<T>());
+            }
+            Ok(Some(value))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn read_exactly_one_value<T: Element>(&mut self) -> EbmlResult<T::Value> {
+        if self.find(&T::get_id(), true)? {
+            let value = self.read_value::<T>()?;
+            if self.find(&T::get_id(), false)? {
+                return Err(self.repeated::<T>());
+            }
+            Ok(value)
+        } else {
+            T::DEFAULT_VALUE.ok_or_else(|| self.missing(T::get_id(), T::NAME))
+        }
+    }
+
+    fn read_zero_or_many_values<T: Element>(&mut self) -> EbmlResult<Vec<T::Value>> {
+        if self.find(&T::get_id(), true)? {
+            let first = self.read_value::<T>()?;
+            let mut result = self.read_following_values::<T>()?;
+            result.insert(0, first);
+            Ok(result)
+        } else {
+            Ok(Vec::new())
+        }
+    }
+
+    fn read_one_or_many_values<T: Element>(&mut self) -> EbmlResult<(T::Value, Vec<T::Value>)> {
+        if self.find(&T::get_id(), true)? {
+            let first = self.read_value::<T>()?;
+            Ok((first, self.read_following_values::<T>()?))
+        } else {
+            T::DEFAULT_VALUE
+                .map(|value| (value, Vec::new()))
+                .ok_or_else(|| self.missing(T::get_id(), T::NAME))
+        }
+    }
+
+    // Reads the values of type `T` which directly follow one that was just read.
+    fn read_following_values<T: Element>(&mut self) -> EbmlResult<Vec<T::Value>> {
+        let mut result = Vec::new();
+        while self.find(&T::get_id(), false)? {
+            result.push(self.read_value::<T>()?);
+        }
+        Ok(result)
+    }
+
+    // Opens the next element as a child container, which must have already been found.
+    fn open_child<NC: Container, NL>(
+        &mut self,
+    ) -> EbmlResult<ContainerReader<NC, NL, R, &mut PeekableReader<R>>> {
+        let mut path = self.path.clone();
+        path.push(NC::NAME);
+        let source = self.source.borrow_mut();
+        let offset = source.position();
+        let parent_path = &self.path;
+        let locate = |err: EbmlError| err.at(offset).within(parent_path);
+        Id::load(source).map_err(locate)?;
+        let child = ContainerReader::new(source, self.end, path)
+            .map_err(|err| locate(err).actual(&NC::get_id()))?;
+        if let (Some(end), Some(child_end)) = (self.end, child.end) {
+            if child_end > end {
+                let context = ErrorContext::at(offset).actual(NC::get_id()).within(parent_path);
+                return Err(EbmlError::SizeOverflow(context));
+            }
+        }
+
+        self.resume = Some(if child.length.get_value().is_some() {
+            Resume::Known(child.end.expect("known size without an end"))
+        } else {
+            Resume::Unknown {
+                may_contain: NC::may_contain,
+                child_types: child_types_of::<NC>,
+                end: child.end,
+            }
+        });
+        Ok(child)
+    }
+}
+/// A reader for a child container of a container of type `C` at depth `L`.
+pub type ChildReader<'a, NC, L, R> =
+    ContainerReader<NC, typenum::Sum<L, typenum::P1>, R, &'a mut PeekableReader<R>>;
+
 impl<C, L, R, B> ContainerReader<C, L, R, B>
 where
     L: Add<typenum::P1>,
     C: Container<ChildOrder = child_order::Significant>,
-    R: Read,
+    R: Source,
     B: BorrowMut<PeekableReader<R>>,
 {
     /// Reads all values in this container of the given type. Use this method when:
     ///
     /// * The element may occur zero or many times in the container.
     /// * The element is restricted by allowed parent, and not by allowed level.
-    pub fn read_zero_or_many_values_by_container<T>(&self) -> EbmlResult<Vec<T::Value>>
+    pub fn read_zero_or_many_values_by_container<T>(&mut self) -> EbmlResult<Vec<T::Value>>
     where
         T: Element<
             Cardinality = cardinality::ZeroOrMany,
@@ -51,24 +569,126 @@ where
             AllowedParent = C,
         >,
     {
-        unimplemented!()
+        self.read_zero_or_many_values::<T>()
+    }
+
+    /// Reads all values in this container of the given type. Use this method when:
+    ///
+    /// * The element may occur zero or many times in the container.
+    /// * The element is restricted by allowed level, and not by allowed parent.
+    pub fn read_zero_or_many_values_by_level<T>(&mut self) -> EbmlResult<Vec<T::Value>>
+    where
+        T: Element<Cardinality = cardinality::ZeroOrMany, AllowedParent = AnyContainer>,
+        T::MaxAllowedLevel: Exceeds<L>,
+        T::MinAllowedLevel: typenum::IsLessOrEqual<typenum::Sum<L, typenum::P1>, Output = typenum::True>,
+    {
+        self.read_zero_or_many_values::<T>()
+    }
+
+    /// Reads the value in this container of the given type. Use this method when:
+    ///
+    /// * The element may occur zero or one times in the container.
+    /// * The element is restricted by allowed parent, and not by allowed level.
+    pub fn read_zero_or_one_value_by_container<T>(&mut self) -> EbmlResult<Option<T::Value>>
+    where
+        T: Element<
+            Cardinality = cardinality::ZeroOrOne,
+            MinAllowedLevel = AnyLevel,
+            MaxAllowedLevel = AnyLevel,
+            AllowedParent = C,
+        >,
+    {
+        self.read_zero_or_one_value::<T>()
     }
 
-    // TODO other value-reading methods
+    /// Reads the value in this container of the given type. Use this method when:
+    ///
+    /// * The element may occur zero or one times in the container.
+    /// * The element is restricted by allowed level, and not by allowed parent.
+    pub fn read_zero_or_one_value_by_level<T>(&mut self) -> EbmlResult<Option<T::Value>>
+    where
+        T: Element<Cardinality = cardinality::ZeroOrOne, AllowedParent = AnyContainer>,
+        T::MaxAllowedLevel: Exceeds<L>,
+        T::MinAllowedLevel: typenum::IsLessOrEqual<typenum::Sum<L, typenum::P1>, Output = typenum::True>,
+    {
+        self.read_zero_or_one_value::<T>()
+    }
+
+    /// Reads the value in this container of the given type. Use this method when:
+    ///
+    /// * The element must occur exactly once in the container.
+    /// * The element is restricted by allowed parent, and not by allowed level.
+    ///
+    /// If the element is absent, its default value is returned if it has one.
+    pub fn read_exactly_one_value_by_container<T>(&mut self) -> EbmlResult<T::Value>
+    where
+        T: Element<
+            Cardinality = cardinality::ExactlyOne,
+            MinAllowedLevel = AnyLevel,
+            MaxAllowedLevel = AnyLevel,
+            AllowedParent = C,
+        >,
+    {
+        self.read_exactly_one_value::<T>()
+    }
 
+    /// Reads the value in this container of the given type. Use this method when:
+    ///
+    /// * The element must occur exactly once in the container.
+    /// * The element is restricted by allowed level, and not by allowed parent.
+    ///
+    /// If the element is absent, its default value is returned if it has one.
+    pub fn read_exactly_one_value_by_level<T>(&mut self) -> EbmlResult<T::Value>
+    where
+        T: Element<Cardinality = cardinality::ExactlyOne, AllowedParent = AnyContainer>,
+        T::MaxAllowedLevel: Exceeds<L>,
+        T::MinAllowedLevel: typenum::IsLessOrEqual<typenum::Sum<L, typenum::P1>, Output = typenum::True>,
+    {
+        self.read_exactly_one_value::<T>()
+    }
+
+    /// Reads the values in this container of the given type. Use this method when:
+    ///
+    /// * The element must occur once but may occur multiple times in the container.
+    /// * The element is restricted by allowed parent, and not by allowed level.
+    ///
+    /// If the element is absent, its default value is returned if it has one.
+    pub fn read_one_or_many_values_by_container<T>(
+        &mut self,
+    ) -> EbmlResult<(T::Value, Vec<T::Value>)>
+    where
+        T: Element<
+            Cardinality = cardinality::OneOrMany,
+            MinAllowedLevel = AnyLevel,
+            MaxAllowedLevel = AnyLevel,
+            AllowedParent = C,
+        >,
+    {
+        self.read_one_or_many_values::<T>()
+    }
+
+    /// Reads the values in this container of the given type. Use this method when:
+    ///
+    /// * The element must occur once but may occur multiple times in the container.
+    /// * The element is restricted by allowed level, and not by allowed parent.
+    ///
+    /// If the element is absent, its default value is returned if it has one.
+    pub fn read_one_or_many_values_by_level<T>(&mut self) -> EbmlResult<(T::Value, Vec<T::Value>)>
+    where
+        T: Element<Cardinality = cardinality::OneOrMany, AllowedParent = AnyContainer>,
+        T::MaxAllowedLevel: Exceeds<L>,
+        T::MinAllowedLevel: typenum::IsLessOrEqual<typenum::Sum<L, typenum::P1>, Output = typenum::True>,
+    {
+        self.read_one_or_many_values::<T>()
+    }
 
     /// Reads the child container of this container of the given type. Use this method when:
     ///
     /// * The child may occur zero or one times in the container.
     /// * The child is restricted by allowed parent, and not by allowed level.
-    pub fn read_zero_or_one_children_by_container<NC: Container>(
-        &mut self
-    ) -> EbmlResult<Option<ContainerReader<
-            NC,
-            typenum::Sum<L, typenum::P1>,
-            R,
-            &mut PeekableReader<R>
-         >>>
+    pub fn read_zero_or_one_child_by_container<NC>(
+        &mut self,
+    ) -> EbmlResult<Option<ChildReader<'_, NC, L, R>>>
     where
         NC: Container<
             Cardinality = cardinality::ZeroOrOne,
@@ -77,29 +697,63 @@ where
             AllowedParent = C,
         >,
     {
-        // Ensure that the ID in the source matches the expected ID. If not, then the element does
-        // not occur and we can return Ok(None). Alternatively, it could be an invalid ID, in which
-        // case the document as a whole is invalid.
-        Ok(if NC::get_id() == Id::load(self.source.borrow_mut())? {
-            Some(ContainerReader {
-                _c: PhantomData, _l: PhantomData, _r: PhantomData,
-
-                length: Size::load(self.source.borrow_mut())?,
-                source: self.source.borrow_mut(),
-            })
+        // If no element up to the end of this container has the expected ID, then the child does
+        // not occur and we can return Ok(None).
+        if self.find(&NC::get_id(), true)? {
+            self.open_child().map(Some)
         } else {
-            None
-        })
+            Ok(None)
+        }
     }
 
-    pub fn read_zero_or_many_children_by_container<NC: Container>(
-        &mut self
-    ) -> EbmlResult<impl Iterator<Item = ContainerReader<
-            NC,
-            typenum::Sum<L, typenum::P1>,
-            R,
-            &mut PeekableReader<R>,
-        >>>
+    /// Reads the child container of this container of the given type. Use this method when:
+    ///
+    /// * The child may occur zero or one times in the container.
+    /// * The child is restricted by allowed level, and not by allowed parent.
+    pub fn read_zero_or_one_child_by_level<NC>(
+        &mut self,
+    ) -> EbmlResult<Option<ChildReader<'_, NC, L, R>>>
+    where
+        NC: Container<Cardinality = cardinality::ZeroOrOne, AllowedParent = AnyContainer>,
+        NC::MaxAllowedLevel: Exceeds<L>,
+        NC::MinAllowedLevel: typenum::IsLessOrEqual<typenum::Sum<L, typenum::P1>, Output = typenum::True>,
+    {
+        if self.find(&NC::get_id(), true)? {
+            self.open_child().map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Reads the child container of this container of the given type. Use this method when:
+    ///
+    /// * The child must occur exactly once in the container.
+    /// * The child is restricted by allowed parent, and not by allowed level.
+    pub fn read_exactly_one_child_by_container<NC>(
+        &mut self,
+    ) -> EbmlResult<ChildReader<'_, NC, L, R>>
+    where
+        NC: Container<
+            Cardinality = cardinality::ExactlyOne,
+            MinAllowedLevel = AnyLevel,
+            MaxAllowedLevel = AnyLevel,
+            AllowedParent = C,
+        >,
+    {
+        if self.find(&NC::get_id(), true)? {
+            self.open_child()
+        } else {
+            Err(self.missing(NC::get_id(), NC::NAME))
+        }
+    }
+
+    /// Reads the child containers of this container of the given type. Use this method when:
+    ///
+    /// * The child may occur zero or many times in the container.
+    /// * The child is restricted by allowed parent, and not by allowed level.
+    pub fn read_zero_or_many_children_by_container<NC>(
+        &mut self,
+    ) -> Children<'_, NC, C, L, R, B>
     where
         NC: Container<
             Cardinality = cardinality::ZeroOrMany,
@@ -108,49 +762,1164 @@ where
             AllowedParent = C,
         >,
     {
-        let mut result = Vec::new();
-        while NC::get_id() == Id::load(self.source.borrow_mut())? {
-            result.push(ContainerReader {
-                _c: PhantomData, _l: PhantomData, _r: PhantomData,
+        Children {
+            _nc: PhantomData,
+            parent: self,
+            found: false,
+        }
+    }
 
-                length: Size::load(self.source.borrow_mut())?,
-                source: self.source.borrow_mut(),
-            });
+    /// Reads the child containers of this container of the given type. Use this method when:
+    ///
+    /// * The child must occur once but may occur multiple times in the container.
+    /// * The child is restricted by allowed parent, and not by allowed level.
+    ///
+    /// Returns an error if there is not at least one child.
+    pub fn read_one_or_many_children_by_container<NC>(
+        &mut self,
+    ) -> EbmlResult<Children<'_, NC, C, L, R, B>>
+    where
+        NC: Container<
+            Cardinality = cardinality::OneOrMany,
+            MinAllowedLevel = AnyLevel,
+            MaxAllowedLevel = AnyLevel,
+            AllowedParent = C,
+        >,
+    {
+        if self.find(&NC::get_id(), true)? {
+            Ok(Children {
+                _nc: PhantomData,
+                parent: self,
+                found: true,
+            })
+        } else {
+            Err(self.missing(NC::get_id(), NC::NAME))
         }
-        Ok(result)
     }
+}
+
+/// A sequence of child containers of type `NC`, read one at a time from a `ContainerReader` with
+/// parameters `C`, `L`, `R` and `B`.
+#[derive(Debug)]
+pub struct Children<'a, NC, C, L, R, B>
+where
+    C: Container + 'a,
+    L: 'a,
+    R: Source + 'a,
+    B: BorrowMut<PeekableReader<R>> + 'a,
+{
+    _nc: PhantomData<NC>,
 
-    // TODO other child-reading methods
+    parent: &'a mut ContainerReader<C, L, R, B>,
+    // Whether a child has been found, after which only the children directly following it are
+    // read.
+    found: bool,
+}
+impl<'a, NC, C, L, R, B> Children<'a, NC, C, L, R, B>
+where
+    NC: Container,
+    C: Container + 'a,
+    L: Add<typenum::P1> + 'a,
+    R: Source + 'a,
+    B: BorrowMut<PeekableReader<R>> + 'a,
+{
+    /// Reads the next child container, returning `None` if there is none. The first child may be
+    /// preceded by other elements, which are skipped; after that, this returns `None` once the
+    /// next element in the parent is not of the child's type.
+    pub fn next_child(&mut self) -> EbmlResult<Option<ChildReader<'_, NC, L, R>>> {
+        if self.parent.find(&NC::get_id(), !self.found)? {
+            self.found = true;
+            self.parent.open_child().map(Some)
+        } else {
+            Ok(None)
+        }
+    }
 }
 
-/// Reads an EBML document, producing the root container.
-pub fn read_document<R: Read>(
-    source: R
-) -> EbmlResult<ContainerReader<EbmlHeader, typenum::Z0, R, PeekableReader<R>>> {
-    let mut source = PeekableReader::new(source)?;
-    if EbmlHeader::get_id() == Id::load(&mut source)? {
-        Ok(ContainerReader {
-            _c: PhantomData, _l: PhantomData, _r: PhantomData,
+/// The highest `EbmlReadVersion` of documents which this crate can read.
+pub const EBML_READ_VERSION: u64 = 1;
 
-            length: Size::load(&mut source)?,
-            source: source,
+/// The contents of the `EbmlHeader` of a document. Elements which are absent have their default
+/// values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+    /// The EBML version used to write the document.
+    pub version: u64,
+    /// The minimum EBML version a reader must support to read the document.
+    pub read_version: u64,
+    /// The maximum width of the IDs in the document.
+    pub max_id_width: u64,
+    /// The maximum width of the sizes in the document.
+    pub max_size_width: u64,
+    /// The type of the document.
+    pub doc_type: String,
+    /// The version of the document type used to write the document.
+    pub doc_type_version: u64,
+    /// The minimum version of the document type a reader must support to read the document.
+    pub doc_type_read_version: u64,
+}
+impl Header {
+    fn from_container(header: &ContainerImpl<EbmlHeader, typenum::Z0>) -> EbmlResult<Self> {
+        Ok(Header {
+            version: header_value::<EbmlVersion>(header)?,
+            read_version: header_value::<EbmlReadVersion>(header)?,
+            max_id_width: header_value::<EbmlMaxIdWidth>(header)?,
+            max_size_width: header_value::<EbmlMaxSizeWidth>(header)?,
+            doc_type: header
+                .get_zero_or_one_value_by_container::<DocType>()?
+                .ok_or_else(missing_from_header::<DocType>)?
+                .to_repr(),
+            doc_type_version: header_value::<DocTypeVersion>(header)?,
+            doc_type_read_version: header_value::<DocTypeReadVersion>(header)?,
         })
+    }
+}
+
+// Gets an unsigned value from the header, or its default value if it is absent.
+fn header_value<T>(header: &ContainerImpl<EbmlHeader, typenum::Z0>) -> EbmlResult<u64>
+where
+    T: Element<
+        Value = UintValue,
+        Cardinality = cardinality::ZeroOrOne,
+        MinAllowedLevel = AnyLevel,
+        MaxAllowedLevel = AnyLevel,
+        AllowedParent = EbmlHeader,
+    >,
+{
+    header
+        .get_zero_or_one_value_by_container::<T>()?
+        .or(T::DEFAULT_VALUE)
+        .map(|value| value.to_repr())
+        .ok_or_else(missing_from_header::<T>)
+}
+
+// An error for an element of type `T` which is missing from the header.
+fn missing_from_header<T: Element>() -> EbmlError {
+    let context = ErrorContext::default().expected(T::get_id()).within(&[EbmlHeader::NAME]);
+    EbmlError::CardinalityViolation(T::NAME, context)
+}
+
+/// A reader for the top-level elements of a document which follow its header.
+pub type DocumentReader<R> = ContainerReader<Document, typenum::N1, R, PeekableReader<R>>;
+
+// Reads the header of the root container of a document, whose IDs are checked as strictly as
+// `validation` demands.
+pub(crate) fn read_root<C: Container, R: Source>(
+    source: R,
+    validation: Validation,
+) -> EbmlResult<ContainerReader<C, typenum::Z0, R, PeekableReader<R>>> {
+    let mut source = PeekableReader::new(source)?;
+    source.set_validation(validation);
+    let id = Id::load(&mut source)?;
+    if C::get_id() == id {
+        ContainerReader::new(source, None, vec![C::NAME]).map_err(|err| err.at(0))
     } else {
-        Err(EbmlError::WrongId)
+        Err(EbmlError::WrongId(ErrorContext::at(0).expected(C::get_id()).actual(id)))
+    }
+}
+
+/// Reads the `EbmlHeader` of an EBML document, and prepares to read the elements following it.
+///
+/// Fails if the document can't be read by a reader which supports EBML up to
+/// `EBML_READ_VERSION`, and its document type up to `doc_type_read_version`. The returned reader
+/// fails with `IdOutOfRange` or `SizeOutOfRange` on IDs and sizes wider than the header allows.
+/// Use `root_container` to load the header without checking it.
+///
+/// IDs which RFC 8794 forbids are rejected with `InvalidVint`; use `read_document_with` to accept
+/// them.
+///
+/// To read a file, wrap it in a `BufReader` and a `Seekable`, so that the elements which are
+/// skipped aren't read at all. A `BufReader<File>` on its own reads through everything it skips.
+pub fn read_document<R: Source>(
+    source: R,
+    doc_type_read_version: u64,
+) -> EbmlResult<(Header, DocumentReader<R>)> {
+    read_document_with(source, doc_type_read_version, Validation::default())
+}
+
+/// Reads the `EbmlHeader` of an EBML document like `read_document`, checking the IDs in the whole
+/// document as strictly as `validation` demands.
+pub fn read_document_with<R: Source>(
+    source: R,
+    doc_type_read_version: u64,
+    validation: Validation,
+) -> EbmlResult<(Header, DocumentReader<R>)> {
+    let (header, source) = read_header(source, doc_type_read_version, validation)?;
+    // The top-level elements aren't enclosed by any container.
+    let body = ContainerReader::with_size(source, UNKNOWN_SIZE, None, Vec::new())?;
+    Ok((header, body))
+}
+
+// Reads and checks the `EbmlHeader` of a document for `read_document_with`, returning the reader
+// positioned after it, with the limits declared by the header.
+pub(crate) fn read_header<R: Source>(
+    source: R,
+    doc_type_read_version: u64,
+    validation: Validation,
+) -> EbmlResult<(Header, PeekableReader<R>)> {
+    let mut source = PeekableReader::new(source)?;
+    source.set_validation(validation);
+    let id = Id::load(&mut source)?;
+    if id != EbmlHeader::get_id() {
+        let context = ErrorContext::at(0).expected(EbmlHeader::get_id()).actual(id);
+        return Err(EbmlError::WrongId(context));
     }
+    let reader = ContainerReader::<EbmlHeader, typenum::Z0, R, _>::new(
+        &mut source,
+        None,
+        vec![EbmlHeader::NAME],
+    ).map_err(|err| err.at(0))?;
+    let container = reader.load()?;
+    let header = Header::from_container(&container)?;
+    if header.read_version > EBML_READ_VERSION {
+        return Err(unsupported::<EbmlReadVersion>(&container));
+    }
+    if header.doc_type_read_version > doc_type_read_version {
+        return Err(unsupported::<DocTypeReadVersion>(&container));
+    }
+
+    // The widths declared by the header apply to everything after it.
+    source.set_limits(container.declared_limits()?.unwrap_or_default());
+    Ok((header, source))
+}
+
+// An error for a read version element of type `T` in the header which is too high.
+fn unsupported<T: Element>(header: &ContainerImpl<EbmlHeader, typenum::Z0>) -> EbmlError {
+    let mut context = ErrorContext::default().actual(T::get_id()).within(&[EbmlHeader::NAME]);
+    context.offset = header.offset_of(&T::get_id());
+    EbmlError::UnsupportedVersion(T::NAME, context)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::io::Cursor;
+    use std_elems::{DocType, DocTypeReadVersion, EbmlMaxIdWidth, EbmlVersion};
+    use {AsciiStringValue, BinaryValue, ChildType, Document, IntValue, UintValue};
 
     #[test]
     fn load_vaild_document() {
         // This is the header of a valid document
         let data = include_bytes!("../tests/min_valid_header");
-        let cursor = Cursor::new(data);
+        let cursor = Cursor::new(&data[..]);
+
+        let doc = read_strict::<EbmlHeader, _>(cursor).unwrap();
+        assert_eq!(31, doc.get_size().get_value().unwrap());
+
+        let header = doc.load().unwrap();
+        assert_eq!(7, header.len().get_value().unwrap());
+        assert_eq!(
+            Some(UintValue::Uint1(1)),
+            header.get_zero_or_one_value_by_container::<EbmlVersion>().unwrap()
+        );
+        assert_eq!(
+            Some(UintValue::Uint1(4)),
+            header.get_zero_or_one_value_by_container::<EbmlMaxIdWidth>().unwrap()
+        );
+        assert_eq!(
+            "webm".parse::<AsciiStringValue>().ok(),
+            header.get_zero_or_one_value_by_container::<DocType>().unwrap()
+        );
+        assert_eq!(
+            Some(UintValue::Uint1(2)),
+            header.get_zero_or_one_value_by_container::<DocTypeReadVersion>().unwrap()
+        );
+    }
+
+    #[test]
+    fn load_wrong_root() {
+        let data = [0x42, 0x86, 0x81, 0x01];
+        assert!(read_document(Cursor::new(&data[..]), 1).is_err());
+    }
+
+    #[test]
+    fn read_header() {
+        let data = include_bytes!("../tests/min_valid_header");
+        let (header, mut body) = read_document(Cursor::new(&data[..]), 2).unwrap();
+        assert_eq!(Header {
+            version: 1,
+            read_version: 1,
+            max_id_width: 4,
+            max_size_width: 8,
+            doc_type: "webm".to_string(),
+            doc_type_version: 2,
+            doc_type_read_version: 2,
+        }, header);
+        assert!(body.next_id().unwrap().is_none());
+
+        match read_document(Cursor::new(&data[..]), 1) {
+            Err(EbmlError::UnsupportedVersion("DocTypeReadVersion", context)) => {
+                assert_eq!(Some(DocTypeReadVersion::get_id()), context.actual);
+                assert_eq!(vec!["EBML"], context.path);
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn read_header_defaults() {
+        let data = [
+            0x1A, 0x45, 0xDF, 0xA3, 0x87,
+                0x42, 0x82, 0x84, b't', b'e', b's', b't',
+            0x1A, 0x45, 0x00, 0x04, 0x83,
+                0x41, 0x01, 0x80,
+            0x1A, 0x45, 0x00, 0x04, 0x80,
+            // The next document
+            0x1A, 0x45, 0xDF, 0xA3, 0x80,
+        ];
+        let (header, mut body) = read_document(Cursor::new(&data[..]), 1).unwrap();
+        assert_eq!(Header {
+            version: 1,
+            read_version: 1,
+            max_id_width: 4,
+            max_size_width: 8,
+            doc_type: "test".to_string(),
+            doc_type_version: 1,
+            doc_type_read_version: 1,
+        }, header);
+
+        // The body ends at the next header.
+        {
+            let mut streams = body.read_zero_or_many_children_by_container::<Stream>();
+            assert!(!streams.next_child().unwrap().unwrap().is_empty());
+            assert!(streams.next_child().unwrap().unwrap().is_empty());
+            assert!(streams.next_child().unwrap().is_none());
+        }
+        assert_eq!(25, body.finish().unwrap());
+    }
+
+    #[test]
+    fn read_invalid_header() {
+        // The document type must be present.
+        let data = [0x1A, 0x45, 0xDF, 0xA3, 0x80];
+        match read_document(Cursor::new(&data[..]), 1) {
+            Err(EbmlError::CardinalityViolation("DocType", _)) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        let data = [
+            0x1A, 0x45, 0xDF, 0xA3, 0x88,
+                0x42, 0xF7, 0x81, 0x02,
+                0x42, 0x82, 0x81, b'a',
+        ];
+        match read_document(Cursor::new(&data[..]), 1) {
+            Err(EbmlError::UnsupportedVersion("EBMLReadVersion", context)) => {
+                assert_eq!(Some(8), context.offset);
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        // The document type must be printable ASCII.
+        let data = [
+            0x1A, 0x45, 0xDF, 0xA3, 0x85,
+                0x42, 0x82, 0x82, b'a', 0x7F,
+        ];
+        match read_document(Cursor::new(&data[..]), 1) {
+            Err(EbmlError::InvalidValue(context)) => {
+                assert_eq!(Some(8), context.offset);
+                assert_eq!(Some(DocType::get_id()), context.actual);
+                assert_eq!(vec!["EBML"], context.path);
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn read_header_limits() {
+        let data = [
+            0x1A, 0x45, 0xDF, 0xA3, 0x8B,
+                0x42, 0x82, 0x84, b't', b'e', b's', b't',
+                0x42, 0xF3, 0x81, 0x01,
+            0x1A, 0x45, 0x00, 0x04, 0x83,
+                0x41, 0x01, 0x80,
+            // The size is wider than the header allows.
+            0x1A, 0x45, 0x00, 0x04, 0x40, 0x03,
+                0x41, 0x01, 0x80,
+        ];
+        let (header, mut body) = read_document(Cursor::new(&data[..]), 1).unwrap();
+        assert_eq!(1, header.max_size_width);
+        let mut streams = body.read_zero_or_many_children_by_container::<Stream>();
+        assert!(!streams.next_child().unwrap().unwrap().is_empty());
+        match streams.next_child() {
+            Err(EbmlError::SizeOutOfRange(context)) => {
+                assert_eq!(Some(28), context.offset);
+                assert_eq!(Some(Stream::get_id()), context.actual);
+            }
+            other => panic!("unexpected result {:?}", other.map(|_| ())),
+        }
+
+        // IDs may not be limited to fewer than 4 bytes.
+        let data = [
+            0x1A, 0x45, 0xDF, 0xA3, 0x88,
+                0x42, 0x82, 0x81, b'a',
+                0x42, 0xF2, 0x81, 0x03,
+        ];
+        match read_document(Cursor::new(&data[..]), 1) {
+            Err(EbmlError::MalformedDocument(context)) => {
+                assert_eq!(Some(12), context.offset);
+                assert_eq!(Some(EbmlMaxIdWidth::get_id()), context.actual);
+            }
+            other => panic!("unexpected result {:?}", other.map(|(header, _)| header)),
+        }
+    }
+
+    // A small document type used to test reading:
+    //
+    // Root (0x1A45_0001)
+    //   Name: binary, exactly one (0x4101)
+    //   Tag: uint, zero or many (0x4102)
+    //   Item: container, zero or many (0x4103)
+    //     Value: int, exactly one (0x4104)
+    //     Part: container, zero or many (0x4108)
+    //   Extra: container, zero or one (0x4105)
+    //   Code: int, one or many (0x4106)
+    //   Global: uint, zero or many, any level (0x4107)
+    // Stream: top level container, zero or many (0x1A45_0004)
+
+    macro_rules! test_container {
+        ($name:ident, $parent:ty, $cardinality:ident, $id:expr, $min:ty, $max:ty,
+         [$($child:expr),*]) => {
+            #[derive(Debug)]
+            enum $name {}
+            impl Container for $name {
+                type Cardinality = cardinality::$cardinality;
+                type ChildOrder = child_order::Significant;
+                type AllowedParent = $parent;
+                type MinAllowedLevel = $min;
+                type MaxAllowedLevel = $max;
+                const NAME: &'static str = stringify!($name);
+
+                fn get_id() -> Id {
+                    Id::from_encoded($id).unwrap()
+                }
+
+                fn child_types() -> Vec<ChildType> {
+                    vec![$($child),*]
+                }
+            }
+        };
+    }
+
+    macro_rules! test_element {
+        ($name:ident, $value:ty, $parent:ty, $cardinality:ident, $id:expr, $min:ty, $max:ty) => {
+            #[derive(Debug)]
+            enum $name {}
+            impl Element for $name {
+                type Value = $value;
+                type Cardinality = cardinality::$cardinality;
+                type AllowedParent = $parent;
+                type MinAllowedLevel = $min;
+                type MaxAllowedLevel = $max;
+                const NAME: &'static str = stringify!($name);
+
+                fn get_id() -> Id {
+                    Id::from_encoded($id).unwrap()
+                }
+            }
+        };
+    }
+
+    test_container!(Root, AnyContainer, ExactlyOne, 0x1A45_0001, typenum::Z0, typenum::Z0, [
+        ChildType::element::<Name>(),
+        ChildType::element::<Tag>(),
+        ChildType::container::<Item>(),
+        ChildType::container::<Extra>(),
+        ChildType::element::<Code>(),
+        ChildType::element::<Global>()
+    ]);
+    test_container!(Stream, Document, ZeroOrMany, 0x1A45_0004, AnyLevel, AnyLevel, [
+        ChildType::element::<Name>()
+    ]);
+    test_container!(Item, Root, ZeroOrMany, 0x4103, AnyLevel, AnyLevel, [
+        ChildType::element::<Value>(),
+        ChildType::container::<Part>(),
+        ChildType::element::<Global>()
+    ]);
+    test_container!(Part, Item, ZeroOrMany, 0x4108, AnyLevel, AnyLevel, [
+        ChildType::element::<Global>()
+    ]);
+    test_container!(Extra, Root, ZeroOrOne, 0x4105, AnyLevel, AnyLevel, [
+        ChildType::element::<Global>()
+    ]);
+    test_element!(Name, BinaryValue, Root, ExactlyOne, 0x4101, AnyLevel, AnyLevel);
+    test_element!(Tag, UintValue, Root, ZeroOrMany, 0x4102, AnyLevel, AnyLevel);
+    test_element!(Value, IntValue, Item, ExactlyOne, 0x4104, AnyLevel, AnyLevel);
+    test_element!(Code, IntValue, Root, OneOrMany, 0x4106, AnyLevel, AnyLevel);
+    test_element!(Global, UintValue, AnyContainer, ZeroOrMany, 0x4107, typenum::P1, typenum::P4);
+
+    fn read_strict<C: Container, R: Source>(
+        source: R,
+    ) -> EbmlResult<ContainerReader<C, typenum::Z0, R, PeekableReader<R>>> {
+        read_root(source, Validation::Strict)
+    }
+
+    fn document(body: &[u8]) -> Vec<u8> {
+        let mut data = vec![0x1A, 0x45, 0x00, 0x01];
+        Size::from_u64(body.len() as u64).unwrap().write(&mut data).unwrap();
+        data.extend_from_slice(body);
+        data
+    }
+
+    #[test]
+    fn read_values() {
+        let data = document(&[
+            0x41, 0x01, 0x82, b'h', b'i',
+            0x41, 0x02, 0x81, 0x01,
+            0xEC, 0x82, 0x00, 0x00, // Void
+            0x41, 0x02, 0x82, 0x01, 0x00,
+            0x41, 0x07, 0x81, 0x07,
+            0x41, 0x06, 0x81, 0xFF,
+            0x41, 0x06, 0x80,
+        ]);
+        let mut root = read_strict::<Root, _>(Cursor::new(data)).unwrap();
+
+        assert_eq!(
+            BinaryValue::from(b"hi"),
+            root.read_exactly_one_value_by_container::<Name>().unwrap()
+        );
+        assert_eq!(
+            vec![UintValue::Uint1(1), UintValue::Uint2(256)],
+            root.read_zero_or_many_values_by_container::<Tag>().unwrap()
+        );
+        assert_eq!(
+            vec![UintValue::Uint1(7)],
+            root.read_zero_or_many_values_by_level::<Global>().unwrap()
+        );
+        assert_eq!(
+            (IntValue::Int1(-1), vec![IntValue::Int0]),
+            root.read_one_or_many_values_by_container::<Code>().unwrap()
+        );
+        assert!(root.read_zero_or_one_child_by_container::<Extra>().unwrap().is_none());
+        assert!(root.next_id().unwrap().is_none());
+        assert!(!root.skip_element().unwrap());
+    }
+
+    #[test]
+    fn read_optional_values() {
+        // Values which may be absent skip the elements preceding them, but only read further
+        // occurrences which follow directly.
+        let data = document(&[
+            0x41, 0x01, 0x80,
+            0x41, 0x07, 0x81, 0x07,
+            0x41, 0x02, 0x81, 0x01,
+            0xEC, 0x80, // Void
+            0x41, 0x02, 0x81, 0x02,
+            0x41, 0x06, 0x81, 0x05,
+            0x41, 0x02, 0x81, 0x03,
+            0x41, 0x03, 0x83,
+                0x41, 0x04, 0x80,
+        ]);
+        let mut root = read_strict::<Root, _>(Cursor::new(data)).unwrap();
+        assert_eq!(
+            vec![UintValue::Uint1(1), UintValue::Uint1(2)],
+            root.read_zero_or_many_values_by_container::<Tag>().unwrap()
+        );
+        assert_eq!(
+            (IntValue::Int1(5), vec![]),
+            root.read_one_or_many_values_by_container::<Code>().unwrap()
+        );
+        {
+            let mut items = root.read_zero_or_many_children_by_container::<Item>();
+            assert!(items.next_child().unwrap().is_some());
+            assert!(items.next_child().unwrap().is_none());
+        }
+        assert!(root.next_id().unwrap().is_none());
+
+        // A value which doesn't occur skips the rest of the container.
+        let data = document(&[
+            0x41, 0x01, 0x80,
+            0x41, 0x06, 0x81, 0x05,
+        ]);
+        let mut root = read_strict::<Root, _>(Cursor::new(data)).unwrap();
+        assert!(root.read_zero_or_one_child_by_container::<Extra>().unwrap().is_none());
+        assert!(root.next_id().unwrap().is_none());
+    }
 
-        let _doc = read_document(cursor).unwrap();
+    #[test]
+    fn read_required_values() {
+        // Required values skip unknown elements, and must be present.
+        let data = document(&[
+            0x41, 0x02, 0x81, 0x01,
+            0x41, 0x01, 0x80,
+        ]);
+        let mut root = read_strict::<Root, _>(Cursor::new(data)).unwrap();
+        assert_eq!(
+            BinaryValue::from(b""),
+            root.read_exactly_one_value_by_container::<Name>().unwrap()
+        );
+        match root.read_one_or_many_values_by_container::<Code>() {
+            Err(EbmlError::CardinalityViolation("Code", context)) => {
+                assert_eq!(ErrorContext {
+                    offset: Some(12),
+                    expected: Some(Code::get_id()),
+                    actual: None,
+                    path: vec!["Root"],
+                }, context);
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        // Values which may occur at most once must do so.
+        let data = document(&[
+            0x41, 0x01, 0x80,
+            0x41, 0x01, 0x80,
+        ]);
+        let mut root = read_strict::<Root, _>(Cursor::new(data)).unwrap();
+        match root.read_exactly_one_value_by_container::<Name>() {
+            Err(EbmlError::CardinalityViolation("Name", context)) => {
+                assert_eq!(ErrorContext {
+                    offset: Some(8),
+                    expected: None,
+                    actual: Some(Name::get_id()),
+                    path: vec!["Root"],
+                }, context);
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn read_children() {
+        let data = document(&[
+            0x41, 0x03, 0x86,
+                0x41, 0x04, 0x81, 0x01,
+                0xEC, 0x80,
+            0x41, 0x03, 0x84,
+                0x41, 0x04, 0x81, 0x02,
+            0x41, 0x03, 0x84,
+                0x41, 0x04, 0x81, 0x03,
+            0x41, 0x05, 0x84,
+                0x41, 0x07, 0x81, 0x04,
+            0x41, 0x06, 0x81, 0x05,
+        ]);
+        let mut root = read_strict::<Root, _>(Cursor::new(data)).unwrap();
+
+        let mut values = Vec::new();
+        {
+            let mut items = root.read_zero_or_many_children_by_container::<Item>();
+            while let Some(mut item) = items.next_child().unwrap() {
+                values.push(item.read_exactly_one_value_by_container::<Value>().unwrap());
+                if values.len() == 2 {
+                    // The rest of the children is skipped even if it's never read.
+                    break;
+                }
+            }
+            let mut item = items.next_child().unwrap().unwrap();
+            assert_eq!(4, item.get_size().get_value().unwrap());
+            assert!(!item.is_empty());
+            item.skip_element().unwrap();
+            assert!(items.next_child().unwrap().is_none());
+        }
+        assert_eq!(vec![IntValue::Int1(1), IntValue::Int1(2)], values);
+
+        {
+            let mut extra = root.read_zero_or_one_child_by_container::<Extra>().unwrap().unwrap();
+            assert_eq!(
+                vec![UintValue::Uint1(4)],
+                extra.read_zero_or_many_values_by_level::<Global>().unwrap()
+            );
+            assert!(extra.next_id().unwrap().is_none());
+        }
+        assert_eq!(
+            (IntValue::Int1(5), vec![]),
+            root.read_one_or_many_values_by_container::<Code>().unwrap()
+        );
+    }
+
+    #[test]
+    fn child_out_of_bounds() {
+        // The child claims to be bigger than its parent.
+        let data = document(&[
+            0x41, 0x03, 0x88,
+                0x41, 0x04, 0x81, 0x01,
+        ]);
+        let mut root = read_strict::<Root, _>(Cursor::new(data)).unwrap();
+        let mut items = root.read_zero_or_many_children_by_container::<Item>();
+        match items.next_child() {
+            Err(EbmlError::SizeOverflow(context)) => {
+                assert_eq!(ErrorContext {
+                    offset: Some(5),
+                    expected: None,
+                    actual: Some(Item::get_id()),
+                    path: vec!["Root"],
+                }, context);
+            }
+            other => panic!("unexpected result {:?}", other.map(|_| ())),
+        }
+
+        // The value claims to be bigger than its parent.
+        let data = document(&[
+            0x41, 0x03, 0x84,
+                0x41, 0x04, 0x82, 0x01,
+            0x41, 0x01, 0x80,
+        ]);
+        let mut root = read_strict::<Root, _>(Cursor::new(data)).unwrap();
+        let mut items = root.read_zero_or_many_children_by_container::<Item>();
+        let mut item = items.next_child().unwrap().unwrap();
+        match item.read_exactly_one_value_by_container::<Value>() {
+            Err(EbmlError::SizeOverflow(context)) => {
+                assert_eq!(Some(8), context.offset);
+                assert_eq!(vec!["Root", "Item"], context.path);
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn error_context() {
+        // The data ends in the middle of a value.
+        let data = [0x1A, 0x45, 0x00, 0x01, 0x88, 0x41, 0x01, 0x85, b'a'];
+        let mut root = read_strict::<Root, _>(Cursor::new(&data[..])).unwrap();
+        match root.read_exactly_one_value_by_container::<Name>() {
+            Err(EbmlError::UnexpectedEof(context)) => {
+                assert_eq!(Some(5), context.offset);
+                assert_eq!(vec!["Root"], context.path);
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        // A size can't start with a zero byte.
+        let data = document(&[0x41, 0x01, 0x00, 0x81, 0x00]);
+        let mut root = read_strict::<Root, _>(Cursor::new(data)).unwrap();
+        match root.read_exactly_one_value_by_container::<Name>() {
+            Err(EbmlError::InvalidVint(context)) => {
+                assert_eq!(Some(7), context.offset);
+                assert_eq!(vec!["Root"], context.path);
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        let data = document(&[]);
+        match read_strict::<Stream, _>(Cursor::new(data)) {
+            Err(EbmlError::WrongId(context)) => {
+                assert_eq!(ErrorContext {
+                    offset: Some(0),
+                    expected: Some(Stream::get_id()),
+                    actual: Some(Root::get_id()),
+                    path: vec![],
+                }, context);
+            }
+            other => panic!("unexpected result {:?}", other.map(|_| ())),
+        }
+    }
+
+    // Checks that reading failed because the data ended early.
+    fn assert_eof<T: ::std::fmt::Debug>(result: EbmlResult<T>) {
+        match result {
+            Err(EbmlError::UnexpectedEof(_)) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn truncated() {
+        let data = document(&[
+            0x41, 0x01, 0x82, b'h', b'i',
+            0x41, 0x03, 0x84,
+                0x41, 0x04, 0x81, 0x01,
+            0x41, 0x06, 0x81, 0x02,
+        ]);
+        for end in 0..data.len() {
+            let data = &data[..end];
+            assert_eof(read_strict::<Root, _>(Cursor::new(data)).and_then(|root| root.load()));
+            assert_eof(read_strict::<Root, _>(Cursor::new(data)).and_then(|root| root.finish()));
+        }
+
+        // Everything before the truncated child is recovered.
+        let root = read_strict::<Root, _>(Cursor::new(&data[..data.len() - 3])).unwrap();
+        let (root, err) = root.load_truncated().unwrap();
+        match err {
+            Some(EbmlError::UnexpectedEof(context)) => {
+                assert_eq!(Some(17), context.offset);
+                assert_eq!(vec!["Root"], context.path);
+            }
+            other => panic!("unexpected error {:?}", other),
+        }
+        assert_eq!(2, root.len().get_value().unwrap());
+
+        let root = read_strict::<Root, _>(Cursor::new(&data[..])).unwrap();
+        let (root, err) = root.load_truncated().unwrap();
+        assert!(err.is_none());
+        assert_eq!(3, root.len().get_value().unwrap());
+
+        // Reading a child which is cut off fails, but the values before it are kept.
+        let mut root = read_strict::<Root, _>(Cursor::new(&data[..15])).unwrap();
+        assert_eq!(
+            BinaryValue::from(b"hi"),
+            root.read_exactly_one_value_by_container::<Name>().unwrap()
+        );
+        let mut items = root.read_zero_or_many_children_by_container::<Item>();
+        let mut item = items.next_child().unwrap().unwrap();
+        assert_eof(item.read_exactly_one_value_by_container::<Value>());
+    }
+
+    #[test]
+    fn validation() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        // 0xFF is reserved, and 0x40 0x01 is an over-long encoding of 0x81.
+        for id in &[&[0xFF][..], &[0x40, 0x01]] {
+            let mut body = vec![0x41, 0x01, 0x82, b'h', b'i'];
+            body.extend_from_slice(id);
+            body.extend_from_slice(&[0x80, 0x41, 0x06, 0x80]);
+            let data = document(&body);
+
+            match read_strict::<Root, _>(Cursor::new(&data[..])).and_then(|root| root.load()) {
+                Err(EbmlError::InvalidVint(context)) => {
+                    assert_eq!(Some(10), context.offset);
+                    assert_eq!(vec!["Root"], context.path);
+                    assert_eq!(Id::from_slice(id).ok(), context.actual);
+                }
+                other => panic!("unexpected result {:?}", other),
+            }
+
+            let warnings = Arc::new(AtomicUsize::new(0));
+            let validation = {
+                let warnings = warnings.clone();
+                Validation::lenient(move |err| {
+                    match *err {
+                        EbmlError::InvalidVint(ref context) => {
+                            assert_eq!(Some(10), context.offset)
+                        }
+                        ref other => panic!("unexpected warning {:?}", other),
+                    }
+                    warnings.fetch_add(1, Ordering::SeqCst);
+                })
+            };
+            let root = read_root::<Root, _>(Cursor::new(&data[..]), validation)
+                .and_then(|root| root.load())
+                .unwrap();
+            assert_eq!(3, root.len().get_value().unwrap());
+            assert_eq!(1, warnings.load(Ordering::SeqCst));
+        }
+    }
+
+    #[test]
+    fn unknown_size_children() {
+        // Unknown-sized children end at the first element which isn't their child.
+        let data = document(&[
+            0x41, 0x03, 0xFF,
+                0x41, 0x04, 0x81, 0x01,
+                0x41, 0x07, 0x81, 0x02,
+            0x41, 0x03, 0xFF,
+                0x41, 0x04, 0x81, 0x03,
+            0x41, 0x06, 0x81, 0x04,
+            0x41, 0x03, 0xFF,
+                0x41, 0x04, 0x81, 0x05,
+        ]);
+        let mut root = read_strict::<Root, _>(Cursor::new(data)).unwrap();
+
+        {
+            let mut items = root.read_zero_or_many_children_by_container::<Item>();
+            let mut item = items.next_child().unwrap().unwrap();
+            assert!(item.get_size().get_value().is_none());
+            assert_eq!(
+                IntValue::Int1(1),
+                item.read_exactly_one_value_by_container::<Value>().unwrap()
+            );
+            assert_eq!(16, item.finish().unwrap());
+
+            // The rest of the child is skipped even if it's never read.
+            let item = items.next_child().unwrap().unwrap();
+            assert!(!item.is_empty());
+            assert!(items.next_child().unwrap().is_none());
+        }
+        assert_eq!(
+            (IntValue::Int1(4), vec![]),
+            root.read_one_or_many_values_by_container::<Code>().unwrap()
+        );
+
+        // The last child ends with its parent.
+        let mut items = root.read_zero_or_many_children_by_container::<Item>();
+        let item = items.next_child().unwrap().unwrap();
+        assert_eq!(34, item.finish().unwrap());
+        assert!(items.next_child().unwrap().is_none());
+    }
+
+    #[test]
+    fn nested_unknown_size_children() {
+        // Unknown-sized children may have unknown-sized children of their own.
+        let data = document(&[
+            0x41, 0x03, 0xFF,
+                0x41, 0x04, 0x81, 0x01,
+                0x41, 0x08, 0xFF,
+                    0x41, 0x07, 0x81, 0x02,
+            0x41, 0x03, 0xFF,
+                0x41, 0x08, 0xFF,
+                    0x41, 0x07, 0x81, 0x03,
+                0x41, 0x04, 0x81, 0x04,
+            0x41, 0x02, 0x81, 0x05,
+            0x41, 0x06, 0x81, 0x06,
+        ]);
+
+        let mut root = read_strict::<Root, _>(Cursor::new(data.clone())).unwrap();
+        assert!(root.skip_element().unwrap());
+        assert!(root.skip_element().unwrap());
+        assert_eq!(Some(Tag::get_id()), root.next_id().unwrap());
+
+        // The items are skipped while looking for the tag.
+        let mut root = read_strict::<Root, _>(Cursor::new(data.clone())).unwrap();
+        assert_eq!(
+            vec![UintValue::Uint1(5)],
+            root.read_zero_or_many_values_by_container::<Tag>().unwrap()
+        );
+        assert_eq!(
+            (IntValue::Int1(6), vec![]),
+            root.read_one_or_many_values_by_container::<Code>().unwrap()
+        );
+
+        let mut root = read_strict::<Root, _>(Cursor::new(data.clone())).unwrap();
+        {
+            let mut items = root.read_zero_or_many_children_by_container::<Item>();
+            let item = items.next_child().unwrap().unwrap();
+            assert_eq!(19, item.finish().unwrap());
+            let mut item = items.next_child().unwrap().unwrap();
+            // The part ends before the value, which it may not contain.
+            {
+                let mut parts = item.read_zero_or_many_children_by_container::<Part>();
+                let part = parts.next_child().unwrap().unwrap();
+                assert_eq!(29, part.finish().unwrap());
+                assert!(parts.next_child().unwrap().is_none());
+            }
+            assert_eq!(
+                IntValue::Int1(4),
+                item.read_exactly_one_value_by_container::<Value>().unwrap()
+            );
+            assert!(items.next_child().unwrap().is_none());
+        }
+        assert_eq!(
+            vec![UintValue::Uint1(5)],
+            root.read_zero_or_many_values_by_container::<Tag>().unwrap()
+        );
+
+        // The parts are loaded along with the items.
+        let root = read_strict::<Root, _>(Cursor::new(data)).unwrap().load().unwrap();
+        assert_eq!(4, root.len().get_value().unwrap());
+    }
+
+    #[test]
+    fn unknown_size_root() {
+        // The root ends at the end of the data, or at the next root.
+        let data = [0x1A, 0x45, 0x00, 0x04, 0xFF, 0x41, 0x01, 0x80];
+        let stream = read_strict::<Stream, _>(Cursor::new(&data[..])).unwrap();
+        assert_eq!(8, stream.finish().unwrap());
+
+        let mut data = data.to_vec();
+        data.extend_from_slice(&data.clone());
+        let mut stream = read_strict::<Stream, _>(Cursor::new(data)).unwrap();
+        assert!(stream.skip_element().unwrap());
+        assert!(!stream.skip_element().unwrap());
+        assert_eq!(8, stream.finish().unwrap());
+    }
+
+    fn with_crc32(body: &[u8]) -> Vec<u8> {
+        let mut data = vec![0xBF, 0x84];
+        data.extend_from_slice(&crc32::encode(crc32::checksum(body)));
+        data.extend_from_slice(body);
+        data
+    }
+
+    #[test]
+    fn read_crc32() {
+        let item = with_crc32(&[0x41, 0x04, 0x81, 0x01]);
+        let mut body = vec![0x41, 0x01, 0x82, b'h', b'i', 0x41, 0x03, 0x8A];
+        body.extend_from_slice(&item);
+        body.extend_from_slice(&[0x41, 0x06, 0x81, 0x03]);
+        let data = document(&with_crc32(&body));
+
+        let mut root = read_strict::<Root, _>(Cursor::new(data.clone())).unwrap();
+        assert_eq!(
+            BinaryValue::from(b"hi"),
+            root.read_exactly_one_value_by_container::<Name>().unwrap()
+        );
+        {
+            let mut items = root.read_zero_or_many_children_by_container::<Item>();
+            let mut item = items.next_child().unwrap().unwrap();
+            assert_eq!(
+                IntValue::Int1(1),
+                item.read_exactly_one_value_by_container::<Value>().unwrap()
+            );
+        }
+        assert_eq!(
+            (IntValue::Int1(3), vec![]),
+            root.read_one_or_many_values_by_container::<Code>().unwrap()
+        );
+        assert!(root.next_id().unwrap().is_none());
+        assert_eq!(3, read_strict::<Root, _>(Cursor::new(data.clone())).unwrap().load().unwrap()
+            .len().get_value().unwrap());
+
+        // The checksum of the root covers the child, which has its own.
+        let mut corrupt = data.clone();
+        let value = corrupt.len() - 5;
+        corrupt[value] = 0x02;
+        let mut root = read_strict::<Root, _>(Cursor::new(corrupt.clone())).unwrap();
+        root.read_exactly_one_value_by_container::<Name>().unwrap();
+        {
+            let mut items = root.read_zero_or_many_children_by_container::<Item>();
+            match items.next_child().unwrap().unwrap().finish() {
+                Err(EbmlError::ChecksumMismatch(ref context)) if context.offset == Some(19) => {}
+                other => panic!("unexpected result {:?}", other),
+            }
+        }
+        let root = read_strict::<Root, _>(Cursor::new(corrupt.clone())).unwrap();
+        match root.load() {
+            Err(EbmlError::ChecksumMismatch(ref context)) if context.offset == Some(5) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        // The checksum is verified even if the data is skipped.
+        corrupt[value] = 0x01;
+        corrupt[14] = b'o';
+        let root = read_strict::<Root, _>(Cursor::new(corrupt)).unwrap();
+        match root.finish() {
+            Err(EbmlError::ChecksumMismatch(ref context)) if context.offset == Some(5) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn read_misplaced_crc32() {
+        // Only the first child of a container holds its checksum; others are skipped.
+        let data = document(&[
+            0x41, 0x01, 0x80,
+            0xBF, 0x84, 0x00, 0x00, 0x00, 0x00,
+            0x41, 0x06, 0x81, 0x03,
+        ]);
+        let mut root = read_strict::<Root, _>(Cursor::new(data)).unwrap();
+        assert!(root.read_exactly_one_value_by_container::<Name>().is_ok());
+        assert_eq!(Some(Code::get_id()), root.next_id().unwrap());
+        assert!(root.read_one_or_many_values_by_container::<Code>().is_ok());
+    }
+
+    #[cfg(feature = "legacy-crc32")]
+    fn crc32_container(body: &[u8]) -> Vec<u8> {
+        let mut data = vec![0xC3];
+        Size::from_u64(body.len() as u64 + 7).unwrap().write(&mut data).unwrap();
+        data.extend_from_slice(&[0x42, 0xFE, 0x84]);
+        data.extend_from_slice(&crc32::encode(crc32::checksum(body)));
+        data.extend_from_slice(body);
+        data
+    }
+
+    #[cfg(feature = "legacy-crc32")]
+    #[test]
+    fn read_legacy_crc32() {
+        // The elements in the CRC-32 container are read as children of the root.
+        let mut body = crc32_container(&[
+            0x41, 0x01, 0x82, b'h', b'i',
+            0x41, 0x03, 0x84,
+                0x41, 0x04, 0x81, 0x01,
+        ]);
+        body.extend_from_slice(&[0x41, 0x03, 0x84, 0x41, 0x04, 0x81, 0x02]);
+        body.extend_from_slice(&[0x41, 0x06, 0x81, 0x03]);
+        let data = document(&body);
+
+        let mut root = read_strict::<Root, _>(Cursor::new(data.clone())).unwrap();
+        assert_eq!(
+            BinaryValue::from(b"hi"),
+            root.read_exactly_one_value_by_container::<Name>().unwrap()
+        );
+        {
+            let mut items = root.read_zero_or_many_children_by_container::<Item>();
+            let mut item = items.next_child().unwrap().unwrap();
+            assert_eq!(
+                IntValue::Int1(1),
+                item.read_exactly_one_value_by_container::<Value>().unwrap()
+            );
+            // The second child is outside the CRC-32 container.
+            assert!(items.next_child().unwrap().is_some());
+            assert!(items.next_child().unwrap().is_none());
+        }
+        assert_eq!(
+            (IntValue::Int1(3), vec![]),
+            root.read_one_or_many_values_by_container::<Code>().unwrap()
+        );
+
+        // The checksum is verified once the data it covers has been passed.
+        let mut corrupt = data.clone();
+        corrupt[17] = b'o';
+        let mut root = read_strict::<Root, _>(Cursor::new(corrupt)).unwrap();
+        assert!(root.read_exactly_one_value_by_container::<Name>().is_ok());
+        match root.read_one_or_many_values_by_container::<Code>() {
+            Err(EbmlError::ChecksumMismatch(ref context)) if context.offset == Some(7) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        // Skipping the covered data still verifies it.
+        let mut corrupt = data.clone();
+        corrupt[25] = 0x05;
+        let root = read_strict::<Root, _>(Cursor::new(corrupt)).unwrap();
+        match root.finish() {
+            Err(EbmlError::ChecksumMismatch(ref context)) if context.offset == Some(7) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        // Loaded containers are verified too.
+        let mut corrupt = data;
+        corrupt[10] ^= 0xFF;
+        let root = read_strict::<Root, _>(Cursor::new(corrupt)).unwrap();
+        match root.load() {
+            Err(EbmlError::ChecksumMismatch(ref context)) if context.offset == Some(7) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[cfg(feature = "legacy-crc32")]
+    #[test]
+    fn read_legacy_crc32_nested() {
+        // CRC-32 containers may be nested in children, and in each other.
+        let inner = crc32_container(&[0x41, 0x04, 0x81, 0x01]);
+        let mut item = vec![0x41, 0x03];
+        Size::from_u64(inner.len() as u64).unwrap().write(&mut item).unwrap();
+        item.extend_from_slice(&inner);
+        let mut body = crc32_container(&crc32_container(&item));
+        body.extend_from_slice(&[0x41, 0x01, 0x80]);
+        let data = document(&body);
+
+        let mut root = read_strict::<Root, _>(Cursor::new(data.clone())).unwrap();
+        {
+            let mut items = root.read_zero_or_many_children_by_container::<Item>();
+            let mut item = items.next_child().unwrap().unwrap();
+            assert_eq!(
+                IntValue::Int1(1),
+                item.read_exactly_one_value_by_container::<Value>().unwrap()
+            );
+        }
+        assert_eq!(
+            BinaryValue::from(b""),
+            root.read_exactly_one_value_by_container::<Name>().unwrap()
+        );
+
+        let loaded = read_strict::<Root, _>(Cursor::new(data.clone())).unwrap().load().unwrap();
+        assert_eq!(2, loaded.len().get_value().unwrap());
+
+        // A child which isn't read to its end is still verified.
+        let mut corrupt = data;
+        let value = corrupt.len() - 4;
+        corrupt[value] ^= 0xFF;
+        let mut root = read_strict::<Root, _>(Cursor::new(corrupt)).unwrap();
+        {
+            let mut items = root.read_zero_or_many_children_by_container::<Item>();
+            let mut item = items.next_child().unwrap().unwrap();
+            assert_eq!(Some(Value::get_id()), item.next_id().unwrap());
+        }
+        match root.read_exactly_one_value_by_container::<Name>() {
+            Err(EbmlError::ChecksumMismatch(_)) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn unknown_size_not_allowed() {
+        // Containers restricted by level may not have an unknown size.
+        let data = [0x1A, 0x45, 0x00, 0x01, 0xFF, 0x41, 0x01, 0x80];
+        match read_strict::<Root, _>(Cursor::new(&data[..])) {
+            Err(EbmlError::UnknownSizeNotAllowed("Root", context)) => {
+                assert_eq!(Some(0), context.offset);
+                assert_eq!(Some(Root::get_id()), context.actual);
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
     }
 }
@@ -2,11 +2,13 @@
 //! Restrictions on the values an `Element` may contain.
 
 use {EbmlValue, IntValue, UintValue, DateValue, FloatValue, FloatValueRepr,
-     StringValue};
+     StringValue, AsciiStringValue, BinaryValue};
+use float10;
 
 #[cfg(feature = "chrono")]
 use chrono::{DateTime, TimeZone, Utc};
 
+use std::cmp::Ordering;
 use std::marker::PhantomData;
 
 /// An additional restriction on the values an element type may contain.
@@ -117,9 +119,7 @@ impl Restriction<UintValue> for UintRangeRestriction {
     }
 }
 
-/// A range which may be open or closed, inclusive or inclusive. Note that Float10's will always
-/// fail this check, even when their value would be in the range, since there is no `f80` type in
-/// Rust.
+/// A range which may be open or closed, inclusive or inclusive.
 #[derive(Debug, Clone)]
 pub enum FloatRangeRestriction {
     /// The range is unbounded on the left, and has a maximum.
@@ -152,52 +152,32 @@ impl Restriction<FloatValue> for FloatRangeRestriction {
     fn matches(&self, value: &FloatValue) -> bool {
         use self::FloatRangeRestriction::*;
 
-        match value.to_repr() {
-            FloatValueRepr::F64(x) => {
-                match *self {
-                    OpenLeft {
-                        max,
-                        inclusive: true,
-                    } => x <= max,
-                    OpenLeft {
-                        max,
-                        inclusive: false,
-                    } => x < max,
-                    OpenRight {
-                        min,
-                        inclusive: true,
-                    } => min <= x,
-                    OpenRight {
-                        min,
-                        inclusive: false,
-                    } => min < x,
-                    Closed {
-                        min,
-                        min_inclusive: true,
-                        max,
-                        max_inclusive: true,
-                    } => min <= x && x <= max,
-                    Closed {
-                        min,
-                        min_inclusive: false,
-                        max,
-                        max_inclusive: true,
-                    } => min < x && x <= max,
-                    Closed {
-                        min,
-                        min_inclusive: true,
-                        max,
-                        max_inclusive: false,
-                    } => min <= x && x < max,
-                    Closed {
-                        min,
-                        min_inclusive: false,
-                        max,
-                        max_inclusive: false,
-                    } => min < x && x < max,
-                }
-            }
-            FloatValueRepr::F80(_) => false,
+        // 10-byte floats are compared exactly, rather than after rounding them to `f64`s.
+        let repr = value.to_repr();
+        let compare = |bound: f64| match repr {
+            FloatValueRepr::F64(x) => x.partial_cmp(&bound),
+            FloatValueRepr::F80(ref x) => float10::compare(x, bound),
+        };
+        let above = |min: f64, inclusive: bool| match compare(min) {
+            Some(Ordering::Greater) => true,
+            Some(Ordering::Equal) => inclusive,
+            _ => false,
+        };
+        let below = |max: f64, inclusive: bool| match compare(max) {
+            Some(Ordering::Less) => true,
+            Some(Ordering::Equal) => inclusive,
+            _ => false,
+        };
+
+        match *self {
+            OpenLeft { max, inclusive } => below(max, inclusive),
+            OpenRight { min, inclusive } => above(min, inclusive),
+            Closed {
+                min,
+                min_inclusive,
+                max,
+                max_inclusive,
+            } => above(min, min_inclusive) && below(max, max_inclusive),
         }
     }
 }
@@ -328,3 +308,43 @@ impl Restriction<StringValue> for [StringRangeRestriction] {
         })
     }
 }
+
+/// A range of legal bytes for a `Binary` or `AsciiString` value, such as the printable ASCII
+/// characters of a `DocType`. Every byte of the value must be in the range.
+#[derive(Debug, Clone)]
+pub enum BinaryRangeRestriction {
+    /// The range consists of a single value.
+    Single(u8),
+    /// The range is closed on both ends.
+    Closed {
+        /// The minimum value.
+        min: u8,
+        /// The maximum value.
+        max: u8,
+    },
+}
+impl BinaryRangeRestriction {
+    /// The printable ASCII characters 0x20 to 0x7E, which `AsciiString` values and therefore
+    /// `DocType`s consist of.
+    pub const PRINTABLE_ASCII: BinaryRangeRestriction =
+        BinaryRangeRestriction::Closed { min: 0x20, max: 0x7E };
+
+    pub(crate) fn matches_byte(&self, b: u8) -> bool {
+        use self::BinaryRangeRestriction::*;
+
+        match *self {
+            Single(allowed) => b == allowed,
+            Closed { min, max } => min <= b && b <= max,
+        }
+    }
+}
+impl Restriction<BinaryValue> for BinaryRangeRestriction {
+    fn matches(&self, value: &BinaryValue) -> bool {
+        value.to_repr().iter().all(|&b| self.matches_byte(b))
+    }
+}
+impl Restriction<AsciiStringValue> for BinaryRangeRestriction {
+    fn matches(&self, value: &AsciiStringValue) -> bool {
+        value.to_repr().bytes().all(|b| self.matches_byte(b))
+    }
+}
@@ -0,0 +1,177 @@
+use std::io::{Read, Write};
+
+use error::{EbmlError, EbmlResult, ErrorContext};
+use size::Size;
+
+/// A signed variable-width integer, as used by Matroska's EBML lacing for the differences between
+/// frame sizes. It is stored as an unsigned variable-width integer of the same width, offset by
+/// half of that width's range, so the range of a `SignedVint` of width `w` is
+/// -(2^(7w - 1) - 1) to 2^(7w - 1) - 1 inclusive. The encoding which would be an unknown size is
+/// reserved.
+///
+/// Each value is equal to itself, regardless of the width it is stored with.
+#[derive(Debug, Clone)]
+pub struct SignedVint {
+    data: Size,
+}
+impl SignedVint {
+    /// Reads a `SignedVint` from a data source, consuming exactly as many bytes as its width.
+    /// Reading from a `&[u8]` advances the slice past the integer.
+    pub fn read<R: Read>(source: &mut R) -> EbmlResult<Self> {
+        let mut buf = [0u8; 8];
+        source.read_exact(&mut buf[..1])?;
+        let width = buf[0].leading_zeros() as usize + 1;
+        if width > 8 {
+            return Err(EbmlError::InvalidVint(ErrorContext::default()));
+        }
+        source.read_exact(&mut buf[1..width])?;
+
+        let data = Size::from_slice(&buf[..width])?;
+        if data.get_value().is_none() {
+            return Err(EbmlError::InvalidVint(ErrorContext::default()));
+        }
+        Ok(SignedVint { data })
+    }
+
+    /// Writes this `SignedVint` to a data sink. The width of the encoding is the width of this
+    /// `SignedVint`; values constructed with `from_i64` always have the minimal width.
+    pub fn write<W: Write>(&self, target: &mut W) -> EbmlResult<()> {
+        self.data.write(target)
+    }
+
+    /// Converts the given value to a `SignedVint` with the given width, which must be between 1
+    /// and 8 inclusive. Returns `None` if the width is out of range or the value can not be stored
+    /// in a `SignedVint` of that width.
+    pub fn with_width(data: i64, width: usize) -> Option<Self> {
+        if !(1..=8).contains(&width) {
+            return None;
+        }
+
+        let bias = bias(width);
+        if data < -bias || data > bias {
+            return None;
+        }
+        Size::with_width((data + bias) as u64, width).map(|data| SignedVint { data })
+    }
+
+    /// Converts the given value to a `SignedVint` of the minimal width, failing if the value is
+    /// out of range (that is, if its magnitude is greater than 2^55 - 1).
+    pub fn from_i64(data: i64) -> Option<Self> {
+        (1..=8).filter_map(|width| Self::with_width(data, width)).next()
+    }
+
+    /// Retrieves the width of this integer (the number of bytes the representation requires).
+    pub fn get_width(&self) -> usize {
+        self.data.get_width()
+    }
+
+    /// Retrieves the value as an `i64`.
+    pub fn get_value(&self) -> i64 {
+        let unsigned = self.data.get_value().expect("signed VINTs are never unknown");
+        unsigned as i64 - bias(self.get_width())
+    }
+}
+impl PartialEq for SignedVint {
+    fn eq(&self, other: &Self) -> bool {
+        self.get_value() == other.get_value()
+    }
+}
+impl Eq for SignedVint {}
+
+// The offset between the stored unsigned value and the signed value, for the given width.
+fn bias(width: usize) -> i64 {
+    (1 << (7 * width - 1)) - 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(x: &SignedVint) -> Vec<u8> {
+        let mut buf = Vec::new();
+        x.write(&mut buf).unwrap();
+        buf
+    }
+
+    fn decode(mut buf: &[u8]) -> EbmlResult<SignedVint> {
+        let x = SignedVint::read(&mut buf)?;
+        assert!(buf.is_empty());
+        Ok(x)
+    }
+
+    #[test]
+    fn write_minimal() {
+        assert_eq!(vec![0xBF], encode(&SignedVint::from_i64(0).unwrap()));
+        assert_eq!(vec![0xC0], encode(&SignedVint::from_i64(1).unwrap()));
+        assert_eq!(vec![0xBE], encode(&SignedVint::from_i64(-1).unwrap()));
+        assert_eq!(vec![0xFE], encode(&SignedVint::from_i64(63).unwrap()));
+        assert_eq!(vec![0x80], encode(&SignedVint::from_i64(-63).unwrap()));
+        assert_eq!(vec![0x60, 0x3F], encode(&SignedVint::from_i64(64).unwrap()));
+        assert_eq!(vec![0x5F, 0xBF], encode(&SignedVint::from_i64(-64).unwrap()));
+        assert_eq!(
+            vec![0x01, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFE],
+            encode(&SignedVint::from_i64((1 << 55) - 1).unwrap())
+        );
+        assert_eq!(
+            vec![0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+            encode(&SignedVint::from_i64(-(1 << 55) + 1).unwrap())
+        );
+
+        assert!(SignedVint::from_i64(1 << 55).is_none());
+        assert!(SignedVint::from_i64(-(1 << 55)).is_none());
+        assert!(SignedVint::from_i64(i64::MIN).is_none());
+    }
+
+    #[test]
+    fn ranges() {
+        for width in 1..=8 {
+            let max = (1i64 << (7 * width - 1)) - 1;
+            for &value in &[-max, -1, 0, 1, max] {
+                let x = SignedVint::with_width(value, width).unwrap();
+                assert_eq!(width, x.get_width());
+                assert_eq!(value, x.get_value());
+            }
+            assert!(SignedVint::with_width(max + 1, width).is_none());
+            assert!(SignedVint::with_width(-max - 1, width).is_none());
+        }
+
+        assert!(SignedVint::with_width(0, 0).is_none());
+        assert!(SignedVint::with_width(0, 9).is_none());
+    }
+
+    #[test]
+    fn round_trip() {
+        let values = [0, 1, -1, 63, -63, 64, -64, 8191, -8191, 8192, 1_000_000, -123_456_789];
+        for &value in &values {
+            let x = SignedVint::from_i64(value).unwrap();
+            let y = decode(&encode(&x)).unwrap();
+            assert_eq!(value, y.get_value());
+            assert_eq!(x.get_width(), y.get_width());
+        }
+
+        let x = SignedVint::with_width(-5, 3).unwrap();
+        assert_eq!(vec![0x2F, 0xFF, 0xFA], encode(&x));
+        let y = decode(&encode(&x)).unwrap();
+        assert_eq!(3, y.get_width());
+        // Values are equal regardless of their widths.
+        assert_eq!(SignedVint::from_i64(-5).unwrap(), y);
+    }
+
+    #[test]
+    fn read_invalid() {
+        // The encodings of unknown sizes are reserved, and the width is at most 8.
+        for data in &[&[0xFF][..], &[0x7F, 0xFF], &[0x00, 0x80]] {
+            match decode(data) {
+                Err(EbmlError::InvalidVint(_)) => {}
+                other => panic!("unexpected result {:?}", other),
+            }
+        }
+        // Cut off.
+        for data in &[&[0x40][..], &[]] {
+            match decode(data) {
+                Err(EbmlError::UnexpectedEof(_)) => {}
+                other => panic!("unexpected result {:?}", other),
+            }
+        }
+    }
+}
@@ -1,9 +1,10 @@
 
 use std::cmp::Ordering;
-use std::io::{Read, Write};
+use std::io::Write;
 
-use error::EbmlResult;
+use error::{EbmlError, EbmlResult, ErrorContext};
 use peek::PeekableReader;
+use source::Source;
 
 // The reserved "unknown" values have these heads and tails of 0xFF.
 const UNKNOWN_HEAD_VALUES: [u8; 8] = [0xFF, 0x7F, 0x3F, 0x1F, 0x0F, 0x07, 0x03, 0x01];
@@ -28,25 +29,100 @@ pub struct Size {
 }
 impl Size {
     /// Attempts to read a `Size` from a data source.
-    pub(crate) fn load<R: Read>(source: &mut PeekableReader<R>) -> EbmlResult<Self> {
-        let (head, tail) = {
-            // read the next 8 bytes, which is the maximum length of a Size
-            let buf = source.peek8();
-            let tail_len = buf[0].leading_zeros() as usize;
-            let mut tail = [0u8; 7];
-            for i in 0..tail_len {
-                tail[i] = buf[1 + i];
-            }
-            (buf[0], tail)
-        };
+    pub(crate) fn load<R: Source>(source: &mut PeekableReader<R>) -> EbmlResult<Self> {
+        let size = Self::peek(source)?;
+        source.advance(size.get_width())?;
+        Ok(size)
+    }
+
+    /// Attempts to read a `Size` from a data source without consuming it. Fails if the `Size` is
+    /// wider than the source allows.
+    pub(crate) fn peek<R: Source>(source: &PeekableReader<R>) -> EbmlResult<Self> {
+        // look at the next 8 bytes, which is the maximum length of a Size
+        let size = Self::from_slice(source.peek8()).map_err(|err| err.at(source.position()))?;
+        source.limits().check_size(&size).map_err(|err| err.at(source.position()))?;
+        Ok(size)
+    }
+
+    /// Attempts to read a `Size` from the start of a buffer. Fails with `UnexpectedEof` if the
+    /// buffer ends before the `Size` does.
+    pub(crate) fn from_slice(buf: &[u8]) -> EbmlResult<Self> {
+        if buf.is_empty() {
+            return Err(EbmlError::UnexpectedEof(ErrorContext::default()));
+        }
+        if buf[0] == 0 {
+            return Err(EbmlError::InvalidVint(ErrorContext::default()));
+        }
 
-        source.advance(1 + head.leading_zeros() as usize)?;
-        Ok(Size { head, tail })
+        let tail_len = buf[0].leading_zeros() as usize;
+        if buf.len() <= tail_len {
+            return Err(EbmlError::UnexpectedEof(ErrorContext::default()));
+        }
+
+        let mut tail = [0u8; 7];
+        tail[..tail_len].copy_from_slice(&buf[1..=tail_len]);
+        Ok(Size {
+            head: buf[0],
+            tail,
+        })
     }
 
-    /// Attempts to write a `Size` to a data sink.
-    pub(crate) fn write<W: Write>(_target: &mut W) -> EbmlResult<()> {
-        unimplemented!("writing not yet supported")
+    /// Writes this `Size` to a data sink. The width of the encoding is the width of this `Size`;
+    /// values constructed with `from_u64` or `From` always have the minimal width.
+    pub fn write<W: Write>(&self, target: &mut W) -> EbmlResult<()> {
+        let tail_len = self.head.leading_zeros() as usize;
+        target.write_all(&[self.head])?;
+        target.write_all(&self.tail[..tail_len])?;
+        Ok(())
+    }
+
+    /// Converts the given value to a `Size` with the given width, which must be between 1 and 8
+    /// inclusive. Returns `None` if the width is out of range or the value can not be stored in a
+    /// `Size` of that width.
+    ///
+    /// This is useful for reserving space for a size which is not known yet, since a wider `Size`
+    /// can always be overwritten in place by a smaller value.
+    pub fn with_width(data: u64, width: usize) -> Option<Self> {
+        if !(1..=8).contains(&width) {
+            return None;
+        }
+
+        // The value gets the 7 * width LSB; all of them being 1 is reserved for unknown sizes.
+        let max = (1u64 << (7 * width)) - 1;
+        if data >= max {
+            return None;
+        }
+
+        let tail_len = width - 1;
+        let mut tail = [0u8; 7];
+        for (i, byte) in tail[..tail_len].iter_mut().enumerate() {
+            // The tail always stores the MSB at position 0.
+            *byte = (data >> (8 * (tail_len - 1 - i))) as u8;
+        }
+        Some(Size {
+            head: (0x80u8 >> tail_len) | (data >> (8 * tail_len)) as u8,
+            tail,
+        })
+    }
+
+    /// Creates a `Size` representing an unknown size with the given width, which must be between 1
+    /// and 8 inclusive. Returns `None` if the width is out of range.
+    ///
+    /// `UNKNOWN_SIZE` is the unknown size of width 1.
+    pub fn unknown_with_width(width: usize) -> Option<Self> {
+        if !(1..=8).contains(&width) {
+            return None;
+        }
+
+        let tail_len = width - 1;
+        let mut tail = [0u8; 7];
+        for byte in &mut tail[..tail_len] {
+            *byte = 0xFF;
+        }
+        Some(Size {
+            head: UNKNOWN_HEAD_VALUES[tail_len],
+            tail,
+        })
     }
 
     /// Retrieves the width of this integer (the number of bytes the representation requires).
@@ -549,4 +625,78 @@ mod tests {
         let x = Size::from_u64(72_057_594_037_927_936);
         assert!(x.is_none());
     }
+
+    fn encode(size: &Size) -> Vec<u8> {
+        let mut buf = Vec::new();
+        size.write(&mut buf).unwrap();
+        buf
+    }
+
+    #[test]
+    fn write_minimal() {
+        assert_eq!(vec![0x80], encode(&0u8.into()));
+        assert_eq!(vec![0x85], encode(&5u8.into()));
+        assert_eq!(vec![0x40, 0x7F], encode(&127u8.into()));
+        assert_eq!(vec![0x4F, 0xA0], encode(&4000u16.into()));
+        assert_eq!(vec![0x20, 0x3F, 0xFF], encode(&16_383u32.into()));
+        assert_eq!(vec![0x10, 0x7F, 0x02, 0x07], encode(&8_323_591u32.into()));
+        assert_eq!(
+            vec![0x07, 0x1D, 0x31, 0x0F, 0x17, 0xD3],
+            encode(&Size::from_u64(3_423_912_007_635).unwrap())
+        );
+        assert_eq!(
+            vec![0x01, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFE],
+            encode(&Size::from_u64(72_057_594_037_927_934).unwrap())
+        );
+    }
+
+    #[test]
+    fn write_with_width() {
+        let x = Size::with_width(5, 1).unwrap();
+        assert_eq!(vec![0x85], encode(&x));
+        assert_eq!(5, x.get_value().unwrap());
+
+        let x = Size::with_width(5, 4).unwrap();
+        assert_eq!(vec![0x10, 0x00, 0x00, 0x05], encode(&x));
+        assert_eq!(4, x.get_width());
+        assert_eq!(5, x.get_value().unwrap());
+
+        let x = Size::with_width(4000, 8).unwrap();
+        assert_eq!(vec![0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x0F, 0xA0], encode(&x));
+        assert_eq!(8, x.get_width());
+        assert_eq!(4000, x.get_value().unwrap());
+
+        let x = Size::with_width(0x3FFE, 2).unwrap();
+        assert_eq!(vec![0x7F, 0xFE], encode(&x));
+        assert_eq!(0x3FFE, x.get_value().unwrap());
+
+        // all 1's is reserved, and wider values don't fit
+        assert!(Size::with_width(127, 1).is_none());
+        assert!(Size::with_width(128, 1).is_none());
+        assert!(Size::with_width(0x3FFF, 2).is_none());
+        assert!(Size::with_width(72_057_594_037_927_935, 8).is_none());
+
+        assert!(Size::with_width(0, 0).is_none());
+        assert!(Size::with_width(0, 9).is_none());
+    }
+
+    #[test]
+    fn write_unknown() {
+        assert_eq!(vec![0xFF], encode(&UNKNOWN_SIZE));
+
+        let x = Size::unknown_with_width(1).unwrap();
+        assert_eq!(UNKNOWN_SIZE, x);
+        assert_eq!(vec![0xFF], encode(&x));
+
+        let x = Size::unknown_with_width(3).unwrap();
+        assert!(x.get_value().is_none());
+        assert_eq!(vec![0x3F, 0xFF, 0xFF], encode(&x));
+
+        let x = Size::unknown_with_width(8).unwrap();
+        assert!(x.get_value().is_none());
+        assert_eq!(vec![0x01, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF], encode(&x));
+
+        assert!(Size::unknown_with_width(0).is_none());
+        assert!(Size::unknown_with_width(9).is_none());
+    }
 }
@@ -0,0 +1,313 @@
+//! Zero-copy reading of EBML documents which are already in memory, such as memory-mapped files.
+//! Instead of copying the data of each element out of a data source, the `SliceReader` splits the
+//! elements off a byte slice, and their data is borrowed from it.
+//!
+//! ```
+//! use ebml::Id;
+//! use ebml::slice::SliceReader;
+//!
+//! let data = [0xA0, 0x85, 0x42, 0x82, 0x82, b'h', b'i'];
+//! let mut reader = SliceReader::new(&data);
+//! let container = reader.next_element().unwrap().unwrap();
+//! assert_eq!(Id::from_encoded(0xA0).unwrap(), *container.id());
+//!
+//! for child in container.children() {
+//!     assert_eq!("hi", child.unwrap().as_str().unwrap());
+//! }
+//! ```
+//!
+//! Every element must have a known size; read documents which contain containers of unknown size
+//! with the `events::Parser` instead.
+
+use {EbmlResult, EbmlValue, Id};
+use container::split_element;
+use error::EbmlError;
+use limits::{Limits, Validation};
+use read::{read_header, Header};
+use value::decode_utf8;
+
+/// Reads the elements in a byte slice, one after another, without copying their data.
+///
+/// The reader implements `Iterator`; it stops after the last element, or after the first error.
+/// `Void` and `Crc32` elements are returned like any other element, and checksums aren't verified.
+#[derive(Debug, Clone)]
+pub struct SliceReader<'a> {
+    // The data which hasn't been read yet.
+    data: &'a [u8],
+    // The absolute position of the start of `data`.
+    position: u64,
+    limits: Limits,
+    validation: Validation,
+    // Whether the data is the contents of a container, rather than the rest of a document.
+    nested: bool,
+    done: bool,
+}
+impl<'a> SliceReader<'a> {
+    /// Creates a reader for the elements in the given data, which starts at position 0. Unlike
+    /// `document`, this doesn't treat the `EbmlHeader` specially. IDs which RFC 8794 forbids are
+    /// rejected with `InvalidVint`.
+    pub fn new(data: &'a [u8]) -> Self {
+        SliceReader::with_validation(data, Validation::default())
+    }
+
+    /// Creates a reader for the elements in the given data like `new`, which checks IDs as
+    /// strictly as `validation` demands.
+    pub fn with_validation(data: &'a [u8], validation: Validation) -> Self {
+        SliceReader {
+            data,
+            position: 0,
+            limits: Limits::default(),
+            validation,
+            nested: false,
+            done: false,
+        }
+    }
+
+    /// Reads the `EbmlHeader` at the start of the given document, and returns a reader for the
+    /// elements following it. This checks the header like `read::read_document`.
+    pub fn document(data: &'a [u8], doc_type_read_version: u64) -> EbmlResult<(Header, Self)> {
+        SliceReader::document_with(data, doc_type_read_version, Validation::default())
+    }
+
+    /// Reads the `EbmlHeader` at the start of the given document like `document`, checking the
+    /// IDs in the whole document as strictly as `validation` demands.
+    pub fn document_with(
+        data: &'a [u8],
+        doc_type_read_version: u64,
+        validation: Validation,
+    ) -> EbmlResult<(Header, Self)> {
+        let (header, source) = read_header(data, doc_type_read_version, validation.clone())?;
+        let position = source.position();
+        let body = SliceReader {
+            data: &data[position as usize..],
+            position,
+            limits: source.limits(),
+            validation,
+            nested: false,
+            done: false,
+        };
+        Ok((header, body))
+    }
+
+    /// The absolute position of the next element.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// The data which hasn't been read yet.
+    pub fn remaining(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Reads the next element, returning `None` once all of the data has been read.
+    pub fn next_element(&mut self) -> EbmlResult<Option<SliceElement<'a>>> {
+        if self.data.is_empty() {
+            return Ok(None);
+        }
+
+        let (id, header, data, rest) =
+            match split_element(self.data, self.position, self.limits, &self.validation, &[]) {
+                // Only a container's children can overflow it; anywhere else the data was cut off.
+                Err(EbmlError::SizeOverflow(context)) if !self.nested => {
+                    return Err(EbmlError::UnexpectedEof(context));
+                }
+                result => result?,
+            };
+        let element = SliceElement {
+            id,
+            offset: self.position,
+            data_offset: self.position + header as u64,
+            data,
+            limits: self.limits,
+            validation: self.validation.clone(),
+        };
+        self.position = element.data_offset + data.len() as u64;
+        self.data = rest;
+        Ok(Some(element))
+    }
+}
+impl<'a> Iterator for SliceReader<'a> {
+    type Item = EbmlResult<SliceElement<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let result = self.next_element();
+        match result {
+            Ok(Some(element)) => Some(Ok(element)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// An element read by a `SliceReader`, whose data is borrowed from the slice it was read from.
+#[derive(Debug, Clone)]
+pub struct SliceElement<'a> {
+    id: Id,
+    offset: u64,
+    data_offset: u64,
+    data: &'a [u8],
+    limits: Limits,
+    validation: Validation,
+}
+impl<'a> SliceElement<'a> {
+    /// The ID of the element.
+    pub fn id(&self) -> &Id {
+        &self.id
+    }
+
+    /// The absolute position of the first byte of the element's ID.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// The absolute position of the first byte of the element's data.
+    pub fn data_offset(&self) -> u64 {
+        self.data_offset
+    }
+
+    /// The undecoded data of the element. For binary elements, this is their value.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Decodes the data of a UTF-8 string element without copying it. Like
+    /// `StringValue::decode`, this treats the first zero byte and everything after it as padding.
+    pub fn as_str(&self) -> EbmlResult<&'a str> {
+        decode_utf8(self.data)
+            .map(|(string, _)| string)
+            .map_err(|err| err.at(self.offset))
+    }
+
+    /// Decodes the data of the element as a value of the given type, copying it if need be.
+    pub fn decode<V: EbmlValue>(&self) -> EbmlResult<V> {
+        V::decode(self.data).map_err(|err| err.at(self.offset))
+    }
+
+    /// Returns a reader for the children of a container element.
+    pub fn children(&self) -> SliceReader<'a> {
+        SliceReader {
+            data: self.data,
+            position: self.data_offset,
+            limits: self.limits,
+            validation: self.validation.clone(),
+            nested: true,
+            done: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {BinaryValue, UintValue};
+
+    fn id(data: u32) -> Id {
+        Id::from_encoded(data).unwrap()
+    }
+
+    #[test]
+    fn elements() {
+        let data = [
+            0xA0, 0x8B,
+                0x42, 0x82, 0x84, b'w', b'e', b'b', 0x00,
+                0x42, 0x86, 0x81, 0x01,
+            0xA1, 0x82, 0xDE, 0xAD,
+        ];
+        let elements: Vec<_> = SliceReader::new(&data).map(|e| e.unwrap()).collect();
+        assert_eq!(2, elements.len());
+        assert_eq!(id(0xA0), *elements[0].id());
+        assert_eq!((0, 2), (elements[0].offset(), elements[0].data_offset()));
+        assert_eq!((13, 15), (elements[1].offset(), elements[1].data_offset()));
+
+        // The data is borrowed from the input.
+        let binary = elements[1].as_bytes();
+        assert_eq!(data[15..].as_ptr(), binary.as_ptr());
+        assert_eq!(BinaryValue::from(&[0xDE, 0xAD]), elements[1].decode().unwrap());
+
+        let children: Vec<_> = elements[0].children().map(|e| e.unwrap()).collect();
+        assert_eq!(2, children.len());
+        assert_eq!(id(0x4282), *children[0].id());
+        let string = children[0].as_str().unwrap();
+        assert_eq!("web", string);
+        assert_eq!(data[5..].as_ptr(), string.as_ptr());
+        assert_eq!((9, 12), (children[1].offset(), children[1].data_offset()));
+        assert_eq!(UintValue::Uint1(1), children[1].decode().unwrap());
+    }
+
+    #[test]
+    fn document() {
+        let mut data = include_bytes!("../tests/min_valid_header").to_vec();
+        let header_len = data.len() as u64;
+        data.extend_from_slice(&[0x42, 0x86, 0x81, 0x01]);
+
+        let (header, mut body) = SliceReader::document(&data, 2).unwrap();
+        assert_eq!("webm", header.doc_type);
+        assert_eq!(header_len, body.position());
+        let element = body.next_element().unwrap().unwrap();
+        assert_eq!(header_len, element.offset());
+        assert_eq!(&[0x01], element.as_bytes());
+        assert!(body.next_element().unwrap().is_none());
+
+        match SliceReader::document(&data, 1) {
+            Err(EbmlError::UnsupportedVersion("DocTypeReadVersion", _)) => {}
+            other => panic!("unexpected result {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn invalid() {
+        // The data is cut off.
+        let mut reader = SliceReader::new(&[0x42, 0x86, 0x81, 0x01, 0x42, 0x86, 0x84, 0x01]);
+        assert!(reader.next().unwrap().is_ok());
+        match reader.next() {
+            Some(Err(EbmlError::UnexpectedEof(ref context))) => assert_eq!(Some(4), context.offset),
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert!(reader.next().is_none());
+
+        // The child is bigger than its parent.
+        let data = [0xA0, 0x83, 0x42, 0x86, 0x82, 0x01];
+        let container = SliceReader::new(&data).next_element().unwrap().unwrap();
+        match container.children().next_element() {
+            Err(EbmlError::SizeOverflow(ref context)) => assert_eq!(Some(2), context.offset),
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        // Sizes must be known.
+        match SliceReader::new(&[0xA0, 0xFF, 0x42, 0x86, 0x81, 0x01]).next_element() {
+            Err(EbmlError::MalformedDocument(ref context)) => {
+                assert_eq!((Some(0), Some(id(0xA0))), (context.offset, context.actual.clone()));
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        // Strings must be valid UTF-8.
+        let data = [0x42, 0x82, 0x82, 0xC3, 0x28];
+        let element = SliceReader::new(&data).next_element().unwrap().unwrap();
+        assert!(element.as_str().is_err());
+    }
+
+    #[test]
+    fn validation() {
+        // 0x40 0x6C is an over-long encoding of the `Void` ID.
+        let data = [0x40, 0x6C, 0x80];
+        match SliceReader::new(&data).next_element() {
+            Err(EbmlError::InvalidVint(ref context)) => assert_eq!(Some(0), context.offset),
+            other => panic!("unexpected result {:?}", other),
+        }
+        let mut reader = SliceReader::with_validation(&data, Validation::lenient(|_| {}));
+        let element = reader.next_element().unwrap().unwrap();
+        assert_eq!(id(0xEC), *element.id());
+        assert!(element.as_bytes().is_empty());
+    }
+}
@@ -0,0 +1,160 @@
+//! The data sources which documents are read from.
+
+use std::cmp;
+use std::io::{self, BufRead, BufReader, Cursor, Read, Seek, SeekFrom};
+
+/// A buffered data source which documents can be read from. Skipping over the data of an element
+/// which isn't needed goes through `skip`, which sources that can jump ahead without reading the
+/// data in between should override.
+///
+/// Wrap unbuffered readers such as `File`s in a `BufReader`, and seekable ones additionally in
+/// `Seekable`. A plain `BufReader<File>` skips by reading all of the data it skips, even though
+/// the file could seek past it; only `Seekable<BufReader<File>>` seeks.
+pub trait Source: BufRead {
+    /// Skips over the given number of bytes, returning the number of bytes skipped. This is less
+    /// than `amount` only if the end of the data was reached first.
+    fn skip(&mut self, amount: u64) -> io::Result<u64> {
+        let mut skipped = 0;
+        while skipped < amount {
+            let available = with_buf(self, |buf| buf.len())? as u64;
+            if available == 0 {
+                break;
+            }
+            let consumed = cmp::min(available, amount - skipped);
+            self.consume(consumed as usize);
+            skipped += consumed;
+        }
+        Ok(skipped)
+    }
+}
+
+impl<R: Read> Source for BufReader<R> {}
+
+impl Source for &[u8] {
+    fn skip(&mut self, amount: u64) -> io::Result<u64> {
+        let skipped = cmp::min(amount, self.len() as u64);
+        *self = &self[skipped as usize..];
+        Ok(skipped)
+    }
+}
+
+impl<T: AsRef<[u8]>> Source for Cursor<T> {
+    fn skip(&mut self, amount: u64) -> io::Result<u64> {
+        let len = self.get_ref().as_ref().len() as u64;
+        let skipped = cmp::min(amount, len.saturating_sub(self.position()));
+        let position = self.position() + skipped;
+        self.set_position(position);
+        Ok(skipped)
+    }
+}
+
+impl<S: Source + ?Sized> Source for &mut S {
+    fn skip(&mut self, amount: u64) -> io::Result<u64> {
+        (**self).skip(amount)
+    }
+}
+
+/// A source which skips data by seeking past it, rather than reading it. This makes skipping large
+/// elements in files cheap.
+///
+/// The length of the data is determined when the `Seekable` is created, so it must not change
+/// while it is read.
+#[derive(Debug)]
+pub struct Seekable<R> {
+    inner: R,
+    len: u64,
+}
+impl<R: BufRead + Seek> Seekable<R> {
+    /// Wraps a seekable source, which is read from its current position.
+    pub fn new(mut inner: R) -> io::Result<Self> {
+        let position = inner.stream_position()?;
+        let len = inner.seek(SeekFrom::End(0))?;
+        inner.seek(SeekFrom::Start(position))?;
+        Ok(Seekable { inner, len })
+    }
+
+    /// Unwraps the source.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+impl<R: BufRead> Read for Seekable<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+impl<R: BufRead> BufRead for Seekable<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.inner.consume(amount)
+    }
+}
+impl<R: BufRead + Seek> Source for Seekable<R> {
+    fn skip(&mut self, amount: u64) -> io::Result<u64> {
+        // Seeking throws the buffer away, so short skips are better served by it.
+        let buffered = with_buf(&mut self.inner, |buf| buf.len())?;
+        if amount <= buffered as u64 {
+            self.inner.consume(amount as usize);
+            return Ok(amount);
+        }
+
+        let position = self.inner.stream_position()?;
+        let target = cmp::min(position.saturating_add(amount), cmp::max(position, self.len));
+        self.inner.seek(SeekFrom::Start(target))?;
+        Ok(target - position)
+    }
+}
+
+/// Passes the buffer of the source to `f`, filling it first if it is empty, and returns the result.
+/// Like `BufRead::fill_buf`, but retries when interrupted.
+pub(crate) fn with_buf<R, F, T>(source: &mut R, mut f: F) -> io::Result<T>
+where
+    R: BufRead + ?Sized,
+    F: FnMut(&[u8]) -> T,
+{
+    // The buffer itself can't be returned from inside the loop without upsetting the borrow
+    // checker, which is why it is passed to a function instead.
+    loop {
+        match source.fill_buf() {
+            Ok(buf) => return Ok(f(buf)),
+            Err(ref err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Skips `amount` bytes of `source`, and reads what is left.
+    fn skip_and_read<S: Source>(mut source: S, amount: u64) -> (u64, Vec<u8>) {
+        let skipped = source.skip(amount).unwrap();
+        let mut rest = Vec::new();
+        source.read_to_end(&mut rest).unwrap();
+        (skipped, rest)
+    }
+
+    #[test]
+    fn skipping() {
+        let data: Vec<u8> = (0..100).collect();
+        for &(amount, skipped) in &[(0, 0), (1, 1), (10, 10), (99, 99), (100, 100), (1000, 100)] {
+            let rest = data[skipped as usize..].to_vec();
+            assert_eq!((skipped, rest.clone()), skip_and_read(&data[..], amount));
+            assert_eq!((skipped, rest.clone()), skip_and_read(Cursor::new(&data), amount));
+            let reader = BufReader::with_capacity(7, &data[..]);
+            assert_eq!((skipped, rest.clone()), skip_and_read(reader, amount));
+            let reader = Seekable::new(BufReader::with_capacity(7, Cursor::new(&data))).unwrap();
+            assert_eq!((skipped, rest), skip_and_read(reader, amount));
+        }
+
+        // Seekable sources are read from their current position.
+        let mut cursor = Cursor::new(&data);
+        cursor.set_position(95);
+        let reader = Seekable::new(BufReader::new(cursor)).unwrap();
+        assert_eq!((5, vec![]), skip_and_read(reader, 10));
+    }
+}
@@ -1,32 +1,55 @@
 
 //! Standard EBML containers common to all documents.
 
-use {AnyContainer, Id, cardinality, child_order};
-use container::Container;
+use {AnyContainer, Id, Unbounded, cardinality, child_order};
+use container::{ChildType, Container};
+use std_elems::{Crc32Value, DocType, DocTypeReadVersion, DocTypeVersion, EbmlMaxIdWidth,
+                EbmlMaxSizeWidth, EbmlReadVersion, EbmlVersion};
 
 use typenum;
 
 //const TEST_ID: Id = Id::new_class_a(0x20).unwrap();
 
 /// The EBML header which all documents must begin with.
+///
+/// RFC 8794 doesn't fix the order of the header's elements, and writers differ in it, so the
+/// order of its children is insignificant: a header is loaded with `ContainerReader::load` and
+/// queried with the `get_*` methods of `ContainerImpl`.
 #[derive(Debug)]
 pub enum EbmlHeader {}
 impl Container for EbmlHeader {
     type Cardinality = cardinality::ZeroOrMany;
-    type ChildOrder = child_order::Significant;
+    type ChildOrder = child_order::Insignificant;
     type AllowedParent = AnyContainer;
     type MinAllowedLevel = typenum::Z0;
-    type MaxAllowedLevel = typenum::P8192;
+    type MaxAllowedLevel = Unbounded;
 
     const NAME: &'static str = "EBML";
 
     fn get_id() -> Id {
         Id::new_class_d(0x0A45DFA3).unwrap()
     }
+
+    fn child_types() -> Vec<ChildType> {
+        vec![
+            ChildType::element::<EbmlVersion>(),
+            ChildType::element::<EbmlReadVersion>(),
+            ChildType::element::<EbmlMaxIdWidth>(),
+            ChildType::element::<EbmlMaxSizeWidth>(),
+            ChildType::element::<DocType>(),
+            ChildType::element::<DocTypeVersion>(),
+            ChildType::element::<DocTypeReadVersion>(),
+        ]
+    }
 }
 
 /// The CRC-32 container can be placed around any element or elements; the value in the
 /// `CRC32Value` element is the CRC-32 checksum over the other elements.
+///
+/// This is the model of early drafts of the EBML specification; RFC 8794 uses the `Crc32` element
+/// instead. With the `legacy-crc32` feature enabled, readers treat the container as transparent:
+/// its elements are read as children of its parent, and the checksum is verified.
+/// `ContainerWriter::write_crc32_container` writes one.
 #[derive(Debug)]
 pub enum Crc32Container {}
 impl Container for Crc32Container {
@@ -34,11 +57,20 @@ impl Container for Crc32Container {
     type ChildOrder = child_order::Significant;
     type AllowedParent = AnyContainer;
     type MinAllowedLevel = typenum::Z0;
-    type MaxAllowedLevel = typenum::P8192;
+    type MaxAllowedLevel = Unbounded;
 
     const NAME: &'static str = "CRC32";
 
     fn get_id() -> Id {
-        Id::new_class_a(0xC3).unwrap()
+        Id::from_encoded(0xC3).unwrap()
+    }
+
+    fn child_types() -> Vec<ChildType> {
+        vec![ChildType::element::<Crc32Value>()]
+    }
+
+    fn may_contain(_: &Id) -> bool {
+        // The checksum may cover any elements.
+        true
     }
 }
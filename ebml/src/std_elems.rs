@@ -1,9 +1,10 @@
 
 //! Standard EBML elements common to all documents.
 
-use {AnyContainer, AnyLevel, BinaryValue, Id, UintValue, Restriction, cardinality};
+use {AnyContainer, AnyLevel, AsciiStringValue, BinaryValue, BinaryRangeRestriction, Id,
+     UintValue, Unbounded, Restriction, cardinality};
 use element::Element;
-use std_containers::EbmlHeader;
+use std_containers::{Crc32Container, EbmlHeader};
 
 use typenum;
 
@@ -20,7 +21,7 @@ impl Element for EbmlVersion {
     const DEFAULT_VALUE: Option<Self::Value> = Some(UintValue::Uint1(1));
 
     fn get_id() -> Id {
-        Id::new_class_b(0x4286).unwrap()
+        Id::from_encoded(0x4286).unwrap()
     }
 }
 
@@ -38,7 +39,7 @@ impl Element for EbmlReadVersion {
     const DEFAULT_VALUE: Option<Self::Value> = Some(UintValue::Uint1(1));
 
     fn get_id() -> Id {
-        Id::new_class_b(0x42F7).unwrap()
+        Id::from_encoded(0x42F7).unwrap()
     }
 }
 
@@ -55,7 +56,7 @@ impl Element for EbmlMaxIdWidth {
     const DEFAULT_VALUE: Option<Self::Value> = Some(UintValue::Uint1(4));
 
     fn get_id() -> Id {
-        Id::new_class_b(0x42F2).unwrap()
+        Id::from_encoded(0x42F2).unwrap()
     }
 }
 
@@ -72,7 +73,7 @@ impl Element for EbmlMaxSizeWidth {
     const DEFAULT_VALUE: Option<Self::Value> = Some(UintValue::Uint1(8));
 
     fn get_id() -> Id {
-        Id::new_class_b(0x42F3).unwrap()
+        Id::from_encoded(0x42F3).unwrap()
     }
 }
 
@@ -80,7 +81,7 @@ impl Element for EbmlMaxSizeWidth {
 #[derive(Debug)]
 pub enum DocType {}
 impl Element for DocType {
-    type Value = BinaryValue;
+    type Value = AsciiStringValue;
     type Cardinality = cardinality::ZeroOrOne;
     type AllowedParent = EbmlHeader;
     type MinAllowedLevel = AnyLevel;
@@ -88,12 +89,11 @@ impl Element for DocType {
     const NAME: &'static str = "DocType";
 
     fn get_id() -> Id {
-        Id::new_class_b(0x4282).unwrap()
+        Id::from_encoded(0x4282).unwrap()
     }
 
-    fn get_restrictions() -> Option<Box<Restriction<BinaryValue>>> {
-        // TODO 32 .. 126
-        unimplemented!()
+    fn get_restrictions() -> Option<Box<dyn Restriction<AsciiStringValue>>> {
+        Some(Box::new(BinaryRangeRestriction::PRINTABLE_ASCII))
     }
 }
 
@@ -110,7 +110,7 @@ impl Element for DocTypeVersion {
     const DEFAULT_VALUE: Option<Self::Value> = Some(UintValue::Uint1(1));
 
     fn get_id() -> Id {
-        Id::new_class_b(0x4287).unwrap()
+        Id::from_encoded(0x4287).unwrap()
     }
 }
 
@@ -128,32 +128,44 @@ impl Element for DocTypeReadVersion {
     const DEFAULT_VALUE: Option<Self::Value> = Some(UintValue::Uint1(1));
 
     fn get_id() -> Id {
-        Id::new_class_b(0x4285).unwrap()
+        Id::from_encoded(0x4285).unwrap()
     }
 }
 
-/// The actual computed CRC-32 checksum over elements in a `Crc32` container.
+/// The actual computed CRC-32 checksum over elements in a `Crc32Container`. It must be the first
+/// child of the container, and its value is the checksum in little-endian byte order.
+///
+/// This is the model of early drafts of the EBML specification; RFC 8794 uses `Crc32` instead.
 #[derive(Debug)]
 pub enum Crc32Value {}
 impl Element for Crc32Value {
     type Value = BinaryValue;
-    type Cardinality = cardinality::ZeroOrOne;
-    type AllowedParent = EbmlHeader;
+    type Cardinality = cardinality::ExactlyOne;
+    type AllowedParent = Crc32Container;
     type MinAllowedLevel = AnyLevel;
     type MaxAllowedLevel = AnyLevel;
     const NAME: &'static str = "CRC32Value";
 
     fn get_id() -> Id {
-        Id::new_class_b(0x42FE).unwrap()
+        Id::from_encoded(0x42FE).unwrap()
     }
+}
+
+/// The CRC-32 checksum of the data of a container following this element, in little-endian byte
+/// order. It may only occur as the first child of a container.
+#[derive(Debug)]
+pub enum Crc32 {}
+impl Element for Crc32 {
+    type Value = BinaryValue;
+    type Cardinality = cardinality::ZeroOrOne;
+    type AllowedParent = AnyContainer;
+    type MinAllowedLevel = typenum::P1;
+    type MaxAllowedLevel = Unbounded;
+    const NAME: &'static str = "CRC-32";
 
-    //fn validate(value: &Value) -> bool {
-    //    if let Some(value) = value.as_binary() {
-    //        value.len() == 4
-    //    } else {
-    //        false
-    //    }
-    //}
+    fn get_id() -> Id {
+        Id::from_encoded(0xBF).unwrap()
+    }
 }
 
 /// An element whose data is ignored.
@@ -165,9 +177,9 @@ impl Element for Void {
     type Cardinality = cardinality::ZeroOrMany;
     type AllowedParent = AnyContainer;
     type MinAllowedLevel = typenum::P1;
-    type MaxAllowedLevel = typenum::P8192;
+    type MaxAllowedLevel = Unbounded;
 
     fn get_id() -> Id {
-        Id::new_class_a(0xEC).unwrap()
+        Id::from_encoded(0xEC).unwrap()
     }
 }
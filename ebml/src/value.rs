@@ -7,6 +7,12 @@ use chrono::{Utc, DateTime, TimeZone, Duration};
 const UNIX_TO_MILLENNIUM_NANOS: i64 = 978_307_200_000_000_000;
 const UNIX_TO_MILLENNIUM_SECONDS: i64 = 978_307_200;
 
+use std::io::Write;
+use std::str::FromStr;
+
+use error::{EbmlError, EbmlResult, ErrorContext};
+use float10;
+use restrictions::BinaryRangeRestriction;
 use size::Size;
 
 /// All EBML leaf values implement this trait.
@@ -26,9 +32,44 @@ pub trait EbmlValue: ::std::fmt::Debug {
 
     /// Copies this value to its Rust representation.
     fn to_repr(&self) -> Self::Repr;
+
+    /// Writes the binary representation of this value to a data sink. Exactly as many bytes as
+    /// `get_size` reports are written.
+    fn encode<W: Write>(&self, target: &mut W) -> EbmlResult<()>;
+
+    /// Reads a value from its binary representation. The length of `data` is the size declared by
+    /// the element containing the value.
+    fn decode(data: &[u8]) -> EbmlResult<Self>
+    where
+        Self: Sized;
+}
+
+/// Decodes UTF-8 string data without copying it, treating the first zero byte and everything after
+/// it as padding. Returns the string and the length of the padding.
+pub(crate) fn decode_utf8(data: &[u8]) -> EbmlResult<(&str, usize)> {
+    let len = data.iter().position(|x| *x == 0).unwrap_or(data.len());
+    let string = ::std::str::from_utf8(&data[..len])
+        .map_err(|_| EbmlError::InvalidValue(ErrorContext::default()))?;
+    Ok((string, data.len() - len))
+}
+
+/// Interprets up to 8 bytes as a big-endian unsigned integer.
+fn read_be(data: &[u8]) -> u64 {
+    data.iter().fold(0u64, |acc, x| (acc << 8) | *x as u64)
+}
+
+/// Interprets up to 8 bytes as a big-endian two's complement integer, sign extending it to 64
+/// bits.
+fn read_be_signed(data: &[u8]) -> i64 {
+    if data.is_empty() {
+        return 0;
+    }
+    let shift = 64 - 8 * data.len() as u32;
+    ((read_be(data) << shift) as i64) >> shift
 }
 
-/// A signed integer.
+/// A signed integer. Values which don't fit in the width of their variant, such as
+/// `Int3(i32::MAX)`, fail to encode with `InvalidValue`.
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub enum IntValue {
     /// A 0-byte signed integer whose only possible value is 0.
@@ -124,9 +165,42 @@ impl EbmlValue for IntValue {
             Int5(x) | Int6(x) | Int7(x) | Int8(x) => x,
         }
     }
+
+    fn encode<W: Write>(&self, target: &mut W) -> EbmlResult<()> {
+        let width = self.get_size().get_value().unwrap_or(0) as usize;
+        let value = self.to_repr();
+        if width > 0 && width < 8 && read_be_signed(&value.to_be_bytes()[8 - width..]) != value {
+            return Err(EbmlError::InvalidValue(ErrorContext::default()));
+        }
+        let data = (value as u64).to_be_bytes();
+        target.write_all(&data[8 - width..])?;
+        Ok(())
+    }
+
+    fn decode(data: &[u8]) -> EbmlResult<Self> {
+        use self::IntValue::*;
+
+        if data.len() > 8 {
+            return Err(EbmlError::InvalidValue(ErrorContext::default()));
+        }
+
+        let value = read_be_signed(data);
+        Ok(match data.len() {
+            0 => Int0,
+            1 => Int1(value as i8),
+            2 => Int2(value as i16),
+            3 => Int3(value as i32),
+            4 => Int4(value as i32),
+            5 => Int5(value),
+            6 => Int6(value),
+            7 => Int7(value),
+            _ => Int8(value),
+        })
+    }
 }
 
-/// An unsigned integer.
+/// An unsigned integer. Values which don't fit in the width of their variant, such as
+/// `Uint5(u64::MAX)`, fail to encode with `InvalidValue`.
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub enum UintValue {
     /// A 0-byte unsigned integer whose only possible value is 0.
@@ -222,10 +296,42 @@ impl EbmlValue for UintValue {
             Uint5(x) | Uint6(x) | Uint7(x) | Uint8(x) => x,
         }
     }
+
+    fn encode<W: Write>(&self, target: &mut W) -> EbmlResult<()> {
+        let width = self.get_size().get_value().unwrap_or(0) as usize;
+        let value = self.to_repr();
+        if width < 8 && value >> (8 * width) != 0 {
+            return Err(EbmlError::InvalidValue(ErrorContext::default()));
+        }
+        let data = value.to_be_bytes();
+        target.write_all(&data[8 - width..])?;
+        Ok(())
+    }
+
+    fn decode(data: &[u8]) -> EbmlResult<Self> {
+        use self::UintValue::*;
+
+        if data.len() > 8 {
+            return Err(EbmlError::InvalidValue(ErrorContext::default()));
+        }
+
+        let value = read_be(data);
+        Ok(match data.len() {
+            0 => Uint0,
+            1 => Uint1(value as u8),
+            2 => Uint2(value as u16),
+            3 => Uint3(value as u32),
+            4 => Uint4(value as u32),
+            5 => Uint5(value),
+            6 => Uint6(value),
+            7 => Uint7(value),
+            _ => Uint8(value),
+        })
+    }
 }
 
 /// A floating-point number.
-#[derive(Debug, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum FloatValue {
     /// A 0-byte IEEE float whose only possible value is 0.0.
     Float0,
@@ -234,9 +340,16 @@ pub enum FloatValue {
     /// A 8-byte IEEE float.
     Float8(f64),
     /// A 10-byte IEEE float. Rust lacks support for x86 extended precision floats, so the actual
-    /// value is stored as binary data.
+    /// value is stored as binary data. Use `FloatValue::float10` to create one from an `f64`, and
+    /// `FloatValueRepr::to_f64` to convert it back.
     Float10([u8; 10]),
 }
+impl FloatValue {
+    /// Creates a 10-byte float with the same value as `x`. The conversion is always exact.
+    pub fn float10(x: f64) -> Self {
+        FloatValue::Float10(float10::from_f64(x))
+    }
+}
 impl From<f32> for FloatValue {
     fn from(data: f32) -> Self {
         if data == 0.0 {
@@ -264,6 +377,37 @@ pub enum FloatValueRepr {
     /// The representation of a 10-byte floating point number.
     F80([u8; 10]),
 }
+impl FloatValueRepr {
+    /// Converts the value to the nearest `f64`, reporting whether it had to be rounded. 10-byte
+    /// floats have a wider range and more precision than `f64`s.
+    pub fn to_f64(&self) -> F64Conversion {
+        match *self {
+            FloatValueRepr::F64(x) => F64Conversion::Exact(x),
+            FloatValueRepr::F80(ref x) => float10::to_f64(x),
+        }
+    }
+}
+
+/// The result of converting a float to an `f64`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum F64Conversion {
+    /// The value was converted exactly.
+    Exact(f64),
+    /// The value was rounded to the nearest `f64`, with ties to even. Values too small for an
+    /// `f64` are rounded to a subnormal or zero with the same sign, and the payloads of NaNs may be
+    /// truncated.
+    Rounded(f64),
+    /// The value was too large for an `f64`, so it was converted to infinity with the same sign.
+    Overflow(f64),
+}
+impl F64Conversion {
+    /// The converted value, however it was converted.
+    pub fn value(&self) -> f64 {
+        match *self {
+            F64Conversion::Exact(x) | F64Conversion::Rounded(x) | F64Conversion::Overflow(x) => x,
+        }
+    }
+}
 impl EbmlValue for FloatValue {
     type Repr = FloatValueRepr;
 
@@ -288,6 +432,34 @@ impl EbmlValue for FloatValue {
             Float10(ref x) => FloatValueRepr::F80(x.clone()),
         }
     }
+
+    fn encode<W: Write>(&self, target: &mut W) -> EbmlResult<()> {
+        use self::FloatValue::*;
+
+        match *self {
+            Float0 => {}
+            Float4(x) => target.write_all(&x.to_bits().to_be_bytes())?,
+            Float8(x) => target.write_all(&x.to_bits().to_be_bytes())?,
+            Float10(ref x) => target.write_all(x)?,
+        }
+        Ok(())
+    }
+
+    fn decode(data: &[u8]) -> EbmlResult<Self> {
+        use self::FloatValue::*;
+
+        Ok(match data.len() {
+            0 => Float0,
+            4 => Float4(f32::from_bits(read_be(data) as u32)),
+            8 => Float8(f64::from_bits(read_be(data))),
+            10 => {
+                let mut x = [0u8; 10];
+                x.copy_from_slice(data);
+                Float10(x)
+            }
+            _ => return Err(EbmlError::InvalidValue(ErrorContext::default())),
+        })
+    }
 }
 
 /// A UTF-8 encoded Unicode string.
@@ -340,6 +512,75 @@ impl EbmlValue for StringValue {
     fn to_repr(&self) -> String {
         self.data.clone()
     }
+
+    fn encode<W: Write>(&self, target: &mut W) -> EbmlResult<()> {
+        target.write_all(self.data.as_bytes())?;
+        for _ in 0..self.padding_len {
+            target.write_all(&[0u8])?;
+        }
+        Ok(())
+    }
+
+    /// Decodes a string, treating the first zero byte and everything after it as padding.
+    fn decode(data: &[u8]) -> EbmlResult<Self> {
+        let (string, padding_len) = decode_utf8(data)?;
+        Ok(StringValue::with_padding(string.to_string(), padding_len))
+    }
+}
+
+/// A printable ASCII string, consisting only of the characters 0x20 to 0x7E. Values are checked
+/// when they are created, so they are always valid.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct AsciiStringValue {
+    data: String,
+    padding_len: usize,
+}
+impl FromStr for AsciiStringValue {
+    type Err = EbmlError;
+
+    /// Creates a value from a string, failing if it isn't printable ASCII.
+    fn from_str(data: &str) -> EbmlResult<Self> {
+        AsciiStringValue::with_padding(data.to_string(), 0)
+    }
+}
+impl AsciiStringValue {
+    /// Creates a string value with some amount of 0-padding appended to it, failing if the string
+    /// isn't printable ASCII. The padding is reflected in the size of the value but not the
+    /// representation.
+    pub fn with_padding(data: String, padding_len: usize) -> EbmlResult<Self> {
+        if data.bytes().all(|b| BinaryRangeRestriction::PRINTABLE_ASCII.matches_byte(b)) {
+            Ok(AsciiStringValue { data, padding_len })
+        } else {
+            Err(EbmlError::InvalidValue(ErrorContext::default()))
+        }
+    }
+}
+impl EbmlValue for AsciiStringValue {
+    type Repr = String;
+
+    fn get_size(&self) -> Size {
+        Size::from_u64((self.data.len() + self.padding_len) as u64)
+            .expect("string + padding too long")
+    }
+
+    fn to_repr(&self) -> String {
+        self.data.clone()
+    }
+
+    fn encode<W: Write>(&self, target: &mut W) -> EbmlResult<()> {
+        target.write_all(self.data.as_bytes())?;
+        for _ in 0..self.padding_len {
+            target.write_all(&[0u8])?;
+        }
+        Ok(())
+    }
+
+    /// Decodes a string, treating the first zero byte and everything after it as padding. Fails if
+    /// the string isn't printable ASCII.
+    fn decode(data: &[u8]) -> EbmlResult<Self> {
+        let (string, padding_len) = decode_utf8(data)?;
+        AsciiStringValue::with_padding(string.to_string(), padding_len)
+    }
 }
 
 /// A timestamp with nanosecond precision.
@@ -417,6 +658,19 @@ impl EbmlValue for DateValue {
             .checked_add(UNIX_TO_MILLENNIUM_NANOS)
             .expect("time out of range")
     }
+
+    fn encode<W: Write>(&self, target: &mut W) -> EbmlResult<()> {
+        target.write_all(&self.nanos_since_millennium.to_be_bytes())?;
+        Ok(())
+    }
+
+    /// Decodes a date. A date with no data represents the millennium itself.
+    fn decode(data: &[u8]) -> EbmlResult<Self> {
+        match data.len() {
+            0 | 8 => Ok(DateValue { nanos_since_millennium: read_be_signed(data) }),
+            _ => Err(EbmlError::InvalidValue(ErrorContext::default())),
+        }
+    }
 }
 
 /// Arbitrary binary data.
@@ -451,6 +705,15 @@ impl EbmlValue for BinaryValue {
     fn to_repr(&self) -> Self::Repr {
         self.data.clone()
     }
+
+    fn encode<W: Write>(&self, target: &mut W) -> EbmlResult<()> {
+        target.write_all(&self.data)?;
+        Ok(())
+    }
+
+    fn decode(data: &[u8]) -> EbmlResult<Self> {
+        Ok(BinaryValue::from_vec(data.to_vec()))
+    }
 }
 
 #[cfg(test)]
@@ -742,4 +1005,243 @@ mod tests {
         assert_eq!(8, x.get_size().get_value().unwrap());
         assert_eq!(sample, x.to_repr());
     }
+
+    fn round_trip<V: EbmlValue>(value: &V) -> (Vec<u8>, V) {
+        let mut buf = Vec::new();
+        value.encode(&mut buf).unwrap();
+        assert_eq!(value.get_size().get_value().unwrap(), buf.len() as u64);
+        let decoded = V::decode(&buf).unwrap();
+        (buf, decoded)
+    }
+
+    #[test]
+    fn encode_decode_unsigned_vals() {
+        let (buf, x) = round_trip(&UintValue::from(0u8));
+        assert!(buf.is_empty());
+        assert_eq!(UintValue::Uint0, x);
+
+        let (buf, x) = round_trip(&UintValue::from(0x01_0203u32));
+        assert_eq!(vec![0x01, 0x02, 0x03], buf);
+        assert_eq!(UintValue::Uint3(0x01_0203), x);
+
+        let (buf, x) = round_trip(&UintValue::from(0xFFFF_FFFF_FFFF_FFFFu64));
+        assert_eq!(vec![0xFF; 8], buf);
+        assert_eq!(UintValue::Uint8(0xFFFF_FFFF_FFFF_FFFF), x);
+
+        // non-minimal encodings keep their width
+        assert_eq!(UintValue::Uint2(1), UintValue::decode(&[0x00, 0x01]).unwrap());
+        assert!(UintValue::decode(&[0; 9]).is_err());
+    }
+
+    #[test]
+    fn encode_out_of_range() {
+        let mut buf = Vec::new();
+        for value in &[UintValue::Uint3(0x0100_0000), UintValue::Uint5(u64::MAX)] {
+            match value.encode(&mut buf) {
+                Err(EbmlError::InvalidValue(_)) => {}
+                other => panic!("unexpected result {:?}", other),
+            }
+        }
+        let values = [
+            IntValue::Int3(i32::MAX),
+            IntValue::Int3(-0x80_0001),
+            IntValue::Int7(i64::MIN),
+        ];
+        for value in &values {
+            match value.encode(&mut buf) {
+                Err(EbmlError::InvalidValue(_)) => {}
+                other => panic!("unexpected result {:?}", other),
+            }
+        }
+        assert!(buf.is_empty());
+
+        // The extremes of each width still fit.
+        let (buf, _) = round_trip(&UintValue::Uint3(0xFF_FFFF));
+        assert_eq!(vec![0xFF; 3], buf);
+        let (buf, _) = round_trip(&IntValue::Int3(-0x80_0000));
+        assert_eq!(vec![0x80, 0x00, 0x00], buf);
+        let (buf, _) = round_trip(&IntValue::Int5(0x7F_FFFF_FFFF));
+        assert_eq!(vec![0x7F, 0xFF, 0xFF, 0xFF, 0xFF], buf);
+    }
+
+    #[test]
+    fn encode_decode_signed_vals() {
+        let (buf, x) = round_trip(&IntValue::from(0i8));
+        assert!(buf.is_empty());
+        assert_eq!(IntValue::Int0, x);
+
+        let (buf, x) = round_trip(&IntValue::from(-1i8));
+        assert_eq!(vec![0xFF], buf);
+        assert_eq!(IntValue::Int1(-1), x);
+
+        let (buf, x) = round_trip(&IntValue::from(-32_769i32));
+        assert_eq!(vec![0xFF, 0x7F, 0xFF], buf);
+        assert_eq!(IntValue::Int3(-32_769), x);
+
+        let (buf, x) = round_trip(&IntValue::from(32_768i32));
+        assert_eq!(vec![0x00, 0x80, 0x00], buf);
+        assert_eq!(IntValue::Int3(32_768), x);
+
+        let (buf, x) = round_trip(&IntValue::from(-2_147_483_649i64));
+        assert_eq!(vec![0xFF, 0x7F, 0xFF, 0xFF, 0xFF], buf);
+        assert_eq!(IntValue::Int5(-2_147_483_649), x);
+
+        let (_, x) = round_trip(&IntValue::from(-549_755_813_889i64));
+        assert_eq!(IntValue::Int6(-549_755_813_889), x);
+
+        let (_, x) = round_trip(&IntValue::from(-140_737_488_355_329i64));
+        assert_eq!(IntValue::Int7(-140_737_488_355_329), x);
+
+        let (_, x) = round_trip(&IntValue::from(140_737_488_355_328i64));
+        assert_eq!(IntValue::Int7(140_737_488_355_328), x);
+
+        let (_, x) = round_trip(&IntValue::from(-36_028_797_018_963_969i64));
+        assert_eq!(IntValue::Int8(-36_028_797_018_963_969), x);
+
+        assert!(IntValue::decode(&[0; 9]).is_err());
+    }
+
+    #[test]
+    fn encode_decode_float_vals() {
+        let (buf, x) = round_trip(&FloatValue::from(0.0f64));
+        assert!(buf.is_empty());
+        assert_eq!(FloatValueRepr::F64(0.0), x.to_repr());
+
+        let (buf, x) = round_trip(&FloatValue::from(1.5f32));
+        assert_eq!(vec![0x3F, 0xC0, 0x00, 0x00], buf);
+        assert_eq!(FloatValue::Float4(1.5), x);
+
+        let (buf, x) = round_trip(&FloatValue::from(-2.0f64));
+        assert_eq!(vec![0xC0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], buf);
+        assert_eq!(FloatValue::Float8(-2.0), x);
+
+        let data = [0x3F, 0xFF, 0x80, 0, 0, 0, 0, 0, 0, 0];
+        let (buf, x) = round_trip(&FloatValue::Float10(data));
+        assert_eq!(data.to_vec(), buf);
+        assert_eq!(FloatValueRepr::F80(data), x.to_repr());
+        assert_eq!(F64Conversion::Exact(1.0), x.to_repr().to_f64());
+
+        let (buf, x) = round_trip(&FloatValue::float10(-0.375));
+        assert_eq!(vec![0xBF, 0xFD, 0xC0, 0, 0, 0, 0, 0, 0, 0], buf);
+        assert_eq!(-0.375, x.to_repr().to_f64().value());
+
+        assert!(FloatValue::decode(&[0; 2]).is_err());
+    }
+
+    #[test]
+    fn float10_range() {
+        use restrictions::{FloatRangeRestriction, Restriction};
+
+        let range = FloatRangeRestriction::Closed {
+            min: 0.0,
+            min_inclusive: false,
+            max: 1.0,
+            max_inclusive: true,
+        };
+        assert!(range.matches(&FloatValue::float10(0.5)));
+        assert!(range.matches(&FloatValue::float10(1.0)));
+        assert!(!range.matches(&FloatValue::float10(0.0)));
+        // Slightly more than 1, although it rounds to 1 as an `f64`.
+        let above = FloatValue::Float10([0x3F, 0xFF, 0x80, 0, 0, 0, 0, 0, 0, 1]);
+        assert_eq!(F64Conversion::Rounded(1.0), above.to_repr().to_f64());
+        assert!(!range.matches(&above));
+    }
+
+    #[test]
+    fn binary_range() {
+        use restrictions::{BinaryRangeRestriction, Restriction};
+
+        let single = BinaryRangeRestriction::Single(0x20);
+        assert!(single.matches(&BinaryValue::from(&[0x20, 0x20])));
+        assert!(!single.matches(&BinaryValue::from(&[0x20, 0x21])));
+
+        let closed = BinaryRangeRestriction::Closed { min: 0x20, max: 0x7E };
+        assert!(closed.matches(&BinaryValue::from(&[0x20, b'a', 0x7E])));
+        assert!(!closed.matches(&BinaryValue::from(&[b'a', 0x7F])));
+        assert!(!closed.matches(&BinaryValue::from(&[0x1F])));
+
+        // Every byte of an empty value is in any range.
+        assert!(single.matches(&BinaryValue::from(&[])));
+        assert!(closed.matches(&BinaryValue::from(&[])));
+
+        // ASCII strings are restricted byte by byte too.
+        let letters = BinaryRangeRestriction::Closed { min: b'a', max: b'z' };
+        assert!(letters.matches(&"webm".parse::<AsciiStringValue>().unwrap()));
+        assert!(!letters.matches(&"WebM".parse::<AsciiStringValue>().unwrap()));
+    }
+
+    #[test]
+    fn encode_decode_string_vals() {
+        let (buf, x) = round_trip(&StringValue::from_str("abc"));
+        assert_eq!(b"abc".to_vec(), buf);
+        assert_eq!("abc".to_string(), x.to_repr());
+
+        let (buf, x) = round_trip(&StringValue::with_padding("ab".into(), 3));
+        assert_eq!(b"ab\0\0\0".to_vec(), buf);
+        assert_eq!(StringValue::with_padding("ab".into(), 3), x);
+
+        let x = StringValue::decode(b"").unwrap();
+        assert_eq!(String::new(), x.to_repr());
+
+        let x = StringValue::decode(b"ab\0c").unwrap();
+        assert_eq!("ab".to_string(), x.to_repr());
+        assert_eq!(4, x.get_size().get_value().unwrap());
+
+        assert!(StringValue::decode(&[0xC3, 0x28]).is_err());
+    }
+
+    #[test]
+    fn encode_decode_ascii_string_vals() {
+        let (buf, x) = round_trip(&"abc".parse::<AsciiStringValue>().unwrap());
+        assert_eq!(b"abc".to_vec(), buf);
+        assert_eq!("abc".to_string(), x.to_repr());
+
+        let x = AsciiStringValue::with_padding("ab".into(), 3).unwrap();
+        let (buf, y) = round_trip(&x);
+        assert_eq!(b"ab\0\0\0".to_vec(), buf);
+        assert_eq!(x, y);
+
+        let x = AsciiStringValue::decode(b"a b~\0c").unwrap();
+        assert_eq!("a b~".to_string(), x.to_repr());
+        assert_eq!(6, x.get_size().get_value().unwrap());
+
+        assert!("tab\t".parse::<AsciiStringValue>().is_err());
+        assert!("caf\u{e9}".parse::<AsciiStringValue>().is_err());
+        assert!(AsciiStringValue::with_padding("\x7F".into(), 1).is_err());
+        match AsciiStringValue::decode(b"ab\x1F") {
+            Err(EbmlError::InvalidValue(_)) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert!(AsciiStringValue::decode(&[b'a', 0xC3, 0xA9]).is_err());
+
+        // Values are checked against the same range as the restriction of `DocType`.
+        for b in 1..0x80u8 {
+            let printable = BinaryRangeRestriction::PRINTABLE_ASCII.matches_byte(b);
+            assert_eq!(printable, AsciiStringValue::decode(&[b]).is_ok());
+        }
+    }
+
+    #[test]
+    fn encode_decode_date_vals() {
+        let x = DateValue { nanos_since_millennium: -1 };
+        let (buf, y) = round_trip(&x);
+        assert_eq!(vec![0xFF; 8], buf);
+        assert_eq!(x, y);
+
+        let x = DateValue::decode(&[]).unwrap();
+        assert_eq!(DateValue { nanos_since_millennium: 0 }, x);
+
+        assert!(DateValue::decode(&[0; 4]).is_err());
+    }
+
+    #[test]
+    fn encode_decode_binary_vals() {
+        let (buf, x) = round_trip(&BinaryValue::from(&[0x01, 0x00, 0x02]));
+        assert_eq!(vec![0x01, 0x00, 0x02], buf);
+        assert_eq!(vec![0x01, 0x00, 0x02], x.to_repr());
+
+        let (buf, x) = round_trip(&BinaryValue::from(&[]));
+        assert!(buf.is_empty());
+        assert!(x.to_repr().is_empty());
+    }
 }
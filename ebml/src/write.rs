@@ -1,36 +1,464 @@
+//! Writing EBML documents
+//!
+//! Writers are constrained by the same element and container types as readers, so writing an
+//! element into a container where it isn't allowed fails to compile:
+//!
+//! ```compile_fail
+//! use ebml::std_elems::DocType;
+//! use ebml::write::write_document;
+//!
+//! let mut document = write_document(Vec::new());
+//! // `DocType` may only occur in an `EbmlHeader`.
+//! let doc_type = "webm".parse().unwrap();
+//! document.write_zero_or_one_value_by_container::<DocType>(Some(doc_type)).unwrap();
+//! ```
+//!
+//! Containers which are restricted by nesting level may not have an unknown size, so they can't be
+//! started in place unless the sink can fill in their size later:
+//!
+//! ```compile_fail
+//! use ebml::std_containers::EbmlHeader;
+//! use ebml::write::write_document;
+//!
+//! let mut document = write_document(Vec::new());
+//! document.start_child_by_level::<EbmlHeader>().unwrap();
+//! ```
 
-//! Reading EBML documents
-
-use std::fs::File;
-use std::io::{Read, Seek, SeekFrom};
+use std::cell::Cell;
+use std::io::{self, Cursor, Seek, SeekFrom, Write};
 use std::marker::PhantomData;
+#[cfg(feature = "legacy-crc32")]
+use std::mem;
 use std::ops::Add;
+use std::rc::Rc;
 
 use typenum;
 
-use {cardinality, child_order, AnyContainer, AnyLevel, EbmlResult, Id, Size};
-use element::Element;
-use std_containers::EbmlHeader;
+use {cardinality, AnyContainer, AnyLevel, Container, ContainerImpl, Document, EbmlResult,
+     EbmlValue, Exceeds, Id, Size, UintValue, UNKNOWN_SIZE};
+use cardinality::Cardinality;
+use container::{check_required, restricted_by_level};
+use crc32;
+use element::{check_restrictions, Element};
+use error::{EbmlError, ErrorContext};
+use limits::{self, Limits};
+#[cfg(feature = "legacy-crc32")]
+use std_containers::Crc32Container;
+use std_elems::Crc32;
+#[cfg(feature = "legacy-crc32")]
+use std_elems::Crc32Value;
+
+/// The width of the sizes reserved for containers by a `Seekable` sink, unless the header of the
+/// document declares a smaller `EbmlMaxSizeWidth`.
+pub const RESERVED_SIZE_WIDTH: usize = 8;
+
+/// A destination for EBML documents, which decides how the sizes of containers are written.
+pub trait Sink: Write {
+    /// Returns the current position, if sizes written at it can be filled in later.
+    fn position(&mut self) -> Option<u64>;
+
+    /// Writes the size of a container which is starting, at most `max_width` bytes wide. If
+    /// `reserve` is true, the size is a placeholder which is filled in by `finish_size` once the
+    /// container is finished; otherwise it is unknown.
+    fn start_size(&mut self, reserve: bool, max_width: usize) -> EbmlResult<()>;
+
+    /// Replaces the size written at the given position by `start_size` with the number of bytes
+    /// written since. `max_width` is the same as was passed to `start_size`. Fails with
+    /// `SizeOutOfRange` if the size doesn't fit into the space reserved for it.
+    fn finish_size(&mut self, position: u64, max_width: usize) -> EbmlResult<()>;
+}
+impl<S: Sink + ?Sized> Sink for &mut S {
+    fn position(&mut self) -> Option<u64> {
+        (**self).position()
+    }
+
+    fn start_size(&mut self, reserve: bool, max_width: usize) -> EbmlResult<()> {
+        (**self).start_size(reserve, max_width)
+    }
+
+    fn finish_size(&mut self, position: u64, max_width: usize) -> EbmlResult<()> {
+        (**self).finish_size(position, max_width)
+    }
+}
 
-/// A source for elements in a container.
+/// A sink which can fill in the sizes of containers once they are finished. Containers which are
+/// restricted by nesting level may not have an unknown size, so they can only be started in place
+/// on these sinks.
+pub trait PatchableSink: Sink {}
+impl<S: PatchableSink + ?Sized> PatchableSink for &mut S {}
+
+/// A sink which only writes forwards. All containers are written with an unknown size.
 #[derive(Debug)]
-pub struct ContainerReader<C: Container, L, R: Read + Seek> {
+pub struct Streaming<W: Write> {
+    inner: W,
+}
+impl<W: Write> Streaming<W> {
+    /// Creates a sink writing to the given writer.
+    pub fn new(inner: W) -> Self {
+        Streaming { inner }
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+impl<W: Write> Write for Streaming<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+impl<W: Write> Sink for Streaming<W> {
+    fn position(&mut self) -> Option<u64> {
+        None
+    }
+
+    fn start_size(&mut self, _: bool, _: usize) -> EbmlResult<()> {
+        UNKNOWN_SIZE.write(&mut self.inner)?;
+        Ok(())
+    }
+
+    fn finish_size(&mut self, _: u64, _: usize) -> EbmlResult<()> {
+        Ok(())
+    }
+}
+
+/// A sink which reserves `RESERVED_SIZE_WIDTH` bytes (or fewer, if the document's header limits
+/// the width of sizes) for the size of each container, and seeks back to fill it in when the
+/// container is finished.
+///
+/// If seeking fails, for example because the writer is a `File` which is actually a pipe, the
+/// container keeps an unknown size instead, or fails to start if it is restricted by nesting
+/// level. Containers which are dropped without being finished also keep an unknown size, as do
+/// containers which are too big for the reserved width, although finishing them fails with
+/// `SizeOutOfRange`.
+#[derive(Debug)]
+pub struct Seekable<W: Write + Seek> {
+    inner: W,
+}
+impl<W: Write + Seek> Seekable<W> {
+    /// Creates a sink writing to the given writer.
+    pub fn new(inner: W) -> Self {
+        Seekable { inner }
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+impl<W: Write + Seek> Write for Seekable<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+impl<W: Write + Seek> PatchableSink for Seekable<W> {}
+impl<W: Write + Seek> Sink for Seekable<W> {
+    fn position(&mut self) -> Option<u64> {
+        self.inner.stream_position().ok()
+    }
+
+    fn start_size(&mut self, reserve: bool, max_width: usize) -> EbmlResult<()> {
+        if reserve {
+            // The reserved size is valid on its own, in case it is never filled in.
+            Size::unknown_with_width(reserved_width(max_width))
+                .expect("invalid reserved size width")
+                .write(&mut self.inner)?;
+        } else {
+            UNKNOWN_SIZE.write(&mut self.inner)?;
+        }
+        Ok(())
+    }
+
+    fn finish_size(&mut self, position: u64, max_width: usize) -> EbmlResult<()> {
+        let width = reserved_width(max_width);
+        let end = self.inner.stream_position()?;
+        let length = end - position - width as u64;
+        let size = Size::with_width(length, width)
+            .ok_or_else(|| EbmlError::SizeOutOfRange(ErrorContext::default()))?;
+        self.inner.seek(SeekFrom::Start(position))?;
+        size.write(&mut self.inner)?;
+        self.inner.seek(SeekFrom::Start(end))?;
+        Ok(())
+    }
+}
+
+// The width of the sizes reserved by a `Seekable` sink.
+fn reserved_width(max_width: usize) -> usize {
+    RESERVED_SIZE_WIDTH.min(max_width)
+}
+
+/// A sink for elements in a container. The parameters are:
+///
+/// C: the container we're writing.
+/// L: the depth of the current container (using typenum).
+/// S: the type of the underlying sink.
+///
+/// Elements are written in the order the methods are called. The sink decides whether the sizes
+/// of child containers are known; containers with an unknown size end at the first element
+/// written after them which is not one of their children.
+///
+/// Writing a second element which may occur at most once is an error, as is finishing a container
+/// without one of its `Container::required_children`. Containers which are dropped without being
+/// finished are not checked.
+///
+/// Once an `EbmlHeader` declaring `EbmlMaxIdWidth` or `EbmlMaxSizeWidth` is finished, writing a
+/// wider ID or size fails with `IdOutOfRange` or `SizeOutOfRange`.
+#[derive(Debug)]
+pub struct ContainerWriter<C: Container, L, S: Sink> {
     _c: PhantomData<C>,
     _l: PhantomData<L>,
 
-    source: R,
+    target: S,
+    // The names of this container and the containers enclosing it, outermost first.
+    path: Vec<&'static str>,
+    // The position and maximum width of this container's size, if it has to be filled in.
+    size_position: Option<(u64, usize)>,
+    // The IDs of the children written so far, without repetitions.
+    written: Vec<Id>,
+    // The limits of the document, shared by the writers of all of its containers.
+    limits: Rc<Cell<Limits>>,
+    // The limits declared by the children written so far, which take effect once this container
+    // is finished.
+    declared: Option<Limits>,
 }
-impl<C, L> ContainerReader<C, L>
+impl<C: Container, L, S: Sink> ContainerWriter<C, L, S> {
+    fn new(
+        target: S,
+        path: Vec<&'static str>,
+        size_position: Option<(u64, usize)>,
+        limits: Rc<Cell<Limits>>,
+    ) -> Self {
+        ContainerWriter {
+            _c: PhantomData,
+            _l: PhantomData,
+
+            target,
+            path,
+            size_position,
+            written: Vec::new(),
+            limits,
+            declared: None,
+        }
+    }
+
+    /// Finishes writing this container, filling in its size if the sink supports it, and returns
+    /// the underlying sink. Finishing the `Document` also flushes the sink.
+    pub fn finish(mut self) -> EbmlResult<S> {
+        check_required::<C, _>(self.written.iter(), &self.path)?;
+        if let Some((position, max_width)) = self.size_position {
+            let parent_path = &self.path[..self.path.len() - 1];
+            self.target
+                .finish_size(position, max_width)
+                .map_err(|err| err.actual(&C::get_id()).within(parent_path))?;
+        }
+        // Only the document has an empty path.
+        if self.path.is_empty() {
+            self.target.flush()?;
+        }
+        if let Some(limits) = self.declared {
+            self.limits.set(limits);
+        }
+        Ok(self.target)
+    }
+
+    // A writer for elements of the container with the given path which are buffered in memory,
+    // sharing the limits of this container.
+    fn buffer<NC: Container, NL>(&self, path: Vec<&'static str>) -> Crc32Writer<NC, NL> {
+        let target = Seekable::new(Cursor::new(Vec::new()));
+        ContainerWriter::new(target, path, None, self.limits.clone())
+    }
+
+    // The path of a child container of type `NC`.
+    fn child_path<NC: Container>(&self) -> Vec<&'static str> {
+        let mut path = self.path.clone();
+        path.push(NC::NAME);
+        path
+    }
+
+    // Checks that a child with the given ID, name and cardinality may be written.
+    fn check_repeat<N: Cardinality>(&self, id: &Id, name: &'static str) -> EbmlResult<()> {
+        if N::AT_MOST_ONCE && self.written.contains(id) {
+            let context = ErrorContext::default().actual(id.clone()).within(&self.path);
+            Err(EbmlError::CardinalityViolation(name, context))
+        } else {
+            Ok(())
+        }
+    }
+
+    // Records that a child with the given ID was written.
+    fn record(&mut self, id: Id) {
+        if !self.written.contains(&id) {
+            self.written.push(id);
+        }
+    }
+
+    fn write_value<T: Element>(&mut self, value: &T::Value) -> EbmlResult<()> {
+        let size = value.get_size();
+        let limits = self.limits.get();
+        let path = self.path.clone();
+        let locate = |err: EbmlError| err.actual(&T::get_id()).within(&path);
+        limits.check_id(&T::get_id()).map_err(locate)?;
+        limits.check_size(&size).map_err(locate)?;
+        check_restrictions::<T>(value).map_err(locate)?;
+        self.check_repeat::<T::Cardinality>(&T::get_id(), T::NAME)?;
+        let mut data = Vec::new();
+        value.encode(&mut data).map_err(locate)?;
+        let declared = limits::declare(self.declared, &T::get_id(), || {
+            UintValue::decode(&data).map(|value| value.to_repr())
+        }).map_err(locate)?;
+        T::get_id().write(&mut self.target)?;
+        size.write(&mut self.target)?;
+        self.target.write_all(&data)?;
+        self.declared = declared;
+        self.record(T::get_id());
+        Ok(())
+    }
+
+    fn write_values<T, I>(&mut self, values: I) -> EbmlResult<()>
+    where
+        T: Element,
+        I: IntoIterator<Item = T::Value>,
+    {
+        for value in values {
+            self.write_value::<T>(&value)?;
+        }
+        Ok(())
+    }
+
+    fn start_child<NC: Container>(&mut self) -> EbmlResult<ChildWriter<'_, NC, L, S>>
+    where
+        L: Add<typenum::P1>,
+    {
+        let limits = self.limits.get();
+        limits.check_id(&NC::get_id()).map_err(|err| err.within(&self.path))?;
+        // Nothing may be written if the container can't be started.
+        let position = self.target.position();
+        if position.is_none() && restricted_by_level::<NC>() {
+            let context = ErrorContext::default().actual(NC::get_id()).within(&self.path);
+            return Err(EbmlError::UnknownSizeNotAllowed(NC::NAME, context));
+        }
+        self.check_repeat::<NC::Cardinality>(&NC::get_id(), NC::NAME)?;
+        NC::get_id().write(&mut self.target)?;
+        let max_width = limits.max_size_width;
+        self.target.start_size(position.is_some(), max_width)?;
+        self.record(NC::get_id());
+        let id_width = NC::get_id().get_width() as u64;
+        let size_position = position.map(|position| (position + id_width, max_width));
+        let path = self.child_path::<NC>();
+        Ok(ContainerWriter::new(&mut self.target, path, size_position, self.limits.clone()))
+    }
+
+    fn write_child<NC: Container, NL>(&mut self, child: &ContainerImpl<NC, NL>) -> EbmlResult<()> {
+        child.check_required()?;
+        let declared = child.declared_limits()?;
+        self.check_repeat::<NC::Cardinality>(&NC::get_id(), NC::NAME)?;
+        child.write_with_limits(&mut self.target, &self.limits.get())?;
+        self.record(NC::get_id());
+        if let Some(limits) = declared {
+            self.limits.set(limits);
+        }
+        Ok(())
+    }
+
+    /// Writes elements into this container inside a `Crc32Container`, which holds the CRC-32
+    /// checksum of their encoding. The elements are written with the writer passed to `write`,
+    /// which buffers them in memory until the checksum is known.
+    ///
+    /// Readers treat the elements as children of this container, so they count towards its
+    /// cardinalities as usual.
+    #[cfg(feature = "legacy-crc32")]
+    pub fn write_crc32_container<F>(&mut self, write: F) -> EbmlResult<()>
+    where
+        F: FnOnce(&mut Crc32Writer<C, L>) -> EbmlResult<()>,
+    {
+        let limits = self.limits.get();
+        let mut buffer = self.buffer(self.path.clone());
+        buffer.written = mem::take(&mut self.written);
+        buffer.declared = self.declared;
+        let result = write(&mut buffer);
+        self.written = buffer.written;
+        self.declared = buffer.declared;
+        result?;
+
+        let data = buffer.target.into_inner().into_inner();
+        let id = Crc32Container::get_id();
+        let path = &self.path;
+        let locate = |err: EbmlError| err.actual(&id).within(path);
+        limits.check_id(&id).map_err(locate)?;
+        id.write(&mut self.target)?;
+        write_checksummed(&mut self.target, &Crc32Value::get_id(), &data, &limits).map_err(locate)
+    }
+
+    fn write_child_with_crc32<NC: Container, F>(&mut self, write: F) -> EbmlResult<()>
+    where
+        L: Add<typenum::P1>,
+        F: FnOnce(&mut Crc32Writer<NC, typenum::Sum<L, typenum::P1>>) -> EbmlResult<()>,
+    {
+        let limits = self.limits.get();
+        limits.check_id(&NC::get_id()).map_err(|err| err.within(&self.path))?;
+        self.check_repeat::<NC::Cardinality>(&NC::get_id(), NC::NAME)?;
+        let mut buffer = self.buffer(self.child_path::<NC>());
+        write(&mut buffer)?;
+        let data = buffer.finish()?.into_inner().into_inner();
+
+        NC::get_id().write(&mut self.target)?;
+        write_checksummed(&mut self.target, &Crc32::get_id(), &data, &limits)
+            .map_err(|err| err.actual(&NC::get_id()).within(&self.path))?;
+        self.record(NC::get_id());
+        Ok(())
+    }
+}
+
+// Writes the size of an element containing the given data preceded by an element with the given
+// ID holding its CRC-32 checksum, and then its data. Fails without writing anything if the size is
+// too wide for the limits.
+fn write_checksummed<W: Write>(
+    target: &mut W,
+    id: &Id,
+    data: &[u8],
+    limits: &Limits,
+) -> EbmlResult<()> {
+    let checksum = crc32::encode(crc32::checksum(data));
+    let checksum_size = Size::from_u64(checksum.len() as u64).expect("invalid checksum size");
+    let length = id.get_width() + checksum_size.get_width() + checksum.len() + data.len();
+    let size = Size::from_u64(length as u64)
+        .ok_or_else(|| EbmlError::SizeOverflow(ErrorContext::default()))?;
+    limits.check_size(&size)?;
+
+    size.write(target)?;
+    id.write(target)?;
+    checksum_size.write(target)?;
+    target.write_all(&checksum)?;
+    target.write_all(data)?;
+    Ok(())
+}
+
+/// A writer for elements covered by a CRC-32 checksum, in a container of type `C` at depth `L`.
+/// The elements are buffered in memory until the checksum is known.
+pub type Crc32Writer<C, L> = ContainerWriter<C, L, Seekable<Cursor<Vec<u8>>>>;
+/// A writer for a child container of a container of type `C` at depth `L`.
+pub type ChildWriter<'a, NC, L, S> = ContainerWriter<NC, typenum::Sum<L, typenum::P1>, &'a mut S>;
+
+impl<C, L, S> ContainerWriter<C, L, S>
 where
     L: Add<typenum::P1>,
-    C: Container<ChildOrder = child_order::Significant>,
-    R: Read,
+    C: Container,
+    S: Sink,
 {
-    /// Reads all values in this container of the given type. Use this method when:
+    /// Writes values of the given type to this container. Use this method when:
     ///
     /// * The element may occur zero or many times in the container.
     /// * The element is restricted by allowed parent, and not by allowed level.
-    pub fn read_zero_or_many_values_by_container<T>(&self) -> EbmlResult<Vec<T::Value>>
+    pub fn write_zero_or_many_values_by_container<T, I>(&mut self, values: I) -> EbmlResult<()>
     where
         T: Element<
             Cardinality = cardinality::ZeroOrMany,
@@ -38,45 +466,835 @@ where
             MaxAllowedLevel = AnyLevel,
             AllowedParent = C,
         >,
+        I: IntoIterator<Item = T::Value>,
     {
-        unimplemented!()
+        self.write_values::<T, I>(values)
     }
 
-    // TODO other value-reading methods
-
+    /// Writes values of the given type to this container. Use this method when:
+    ///
+    /// * The element may occur zero or many times in the container.
+    /// * The element is restricted by allowed level, and not by allowed parent.
+    pub fn write_zero_or_many_values_by_level<T, I>(&mut self, values: I) -> EbmlResult<()>
+    where
+        T: Element<Cardinality = cardinality::ZeroOrMany, AllowedParent = AnyContainer>,
+        T::MaxAllowedLevel: Exceeds<L>,
+        T::MinAllowedLevel: typenum::IsLessOrEqual<typenum::Sum<L, typenum::P1>, Output = typenum::True>,
+        I: IntoIterator<Item = T::Value>,
+    {
+        self.write_values::<T, I>(values)
+    }
 
-    /// Reads the child container of this container of the given type. Use this method when:
+    /// Writes a value of the given type to this container, if there is one. Use this method when:
     ///
-    /// * The child may occur zero or one times in the container.
-    /// * The child is restricted by allowed parent, and not by allowed level.
-    pub fn read_zero_or_one_children_by_container<NC: Container>(
-        &self
-    ) -> EbmlResult<Option<ContainerReader<NC, typenum::Sum<L, typenum::P1>, R>>>
+    /// * The element may occur zero or one times in the container.
+    /// * The element is restricted by allowed parent, and not by allowed level.
+    pub fn write_zero_or_one_value_by_container<T>(
+        &mut self,
+        value: Option<T::Value>,
+    ) -> EbmlResult<()>
     where
-        NC: Container<
+        T: Element<
             Cardinality = cardinality::ZeroOrOne,
             MinAllowedLevel = AnyLevel,
             MaxAllowedLevel = AnyLevel,
             AllowedParent = C,
         >,
     {
-        // Ensure that the ID in the source matches the expected ID. If not, then the element does
-        // not occur and we can return Ok(None). Alternatively, it could be an invalid ID, in which
-        // case the document as a whole is invalid.
-        let expected_id = NC::get_id();
-        let actual_id = Id::load(&self.source);
+        self.write_values::<T, _>(value)
+    }
+
+    /// Writes a value of the given type to this container, if there is one. Use this method when:
+    ///
+    /// * The element may occur zero or one times in the container.
+    /// * The element is restricted by allowed level, and not by allowed parent.
+    pub fn write_zero_or_one_value_by_level<T>(&mut self, value: Option<T::Value>) -> EbmlResult<()>
+    where
+        T: Element<Cardinality = cardinality::ZeroOrOne, AllowedParent = AnyContainer>,
+        T::MaxAllowedLevel: Exceeds<L>,
+        T::MinAllowedLevel: typenum::IsLessOrEqual<typenum::Sum<L, typenum::P1>, Output = typenum::True>,
+    {
+        self.write_values::<T, _>(value)
+    }
+
+    /// Writes the value of the given type to this container. Use this method when:
+    ///
+    /// * The element must occur exactly once in the container.
+    /// * The element is restricted by allowed parent, and not by allowed level.
+    pub fn write_exactly_one_value_by_container<T>(&mut self, value: T::Value) -> EbmlResult<()>
+    where
+        T: Element<
+            Cardinality = cardinality::ExactlyOne,
+            MinAllowedLevel = AnyLevel,
+            MaxAllowedLevel = AnyLevel,
+            AllowedParent = C,
+        >,
+    {
+        self.write_value::<T>(&value)
+    }
+
+    /// Writes the value of the given type to this container. Use this method when:
+    ///
+    /// * The element must occur exactly once in the container.
+    /// * The element is restricted by allowed level, and not by allowed parent.
+    pub fn write_exactly_one_value_by_level<T>(&mut self, value: T::Value) -> EbmlResult<()>
+    where
+        T: Element<Cardinality = cardinality::ExactlyOne, AllowedParent = AnyContainer>,
+        T::MaxAllowedLevel: Exceeds<L>,
+        T::MinAllowedLevel: typenum::IsLessOrEqual<typenum::Sum<L, typenum::P1>, Output = typenum::True>,
+    {
+        self.write_value::<T>(&value)
+    }
+
+    /// Writes values of the given type to this container. Use this method when:
+    ///
+    /// * The element must occur once but may occur multiple times in the container.
+    /// * The element is restricted by allowed parent, and not by allowed level.
+    pub fn write_one_or_many_values_by_container<T, I>(
+        &mut self,
+        first: T::Value,
+        rest: I,
+    ) -> EbmlResult<()>
+    where
+        T: Element<
+            Cardinality = cardinality::OneOrMany,
+            MinAllowedLevel = AnyLevel,
+            MaxAllowedLevel = AnyLevel,
+            AllowedParent = C,
+        >,
+        I: IntoIterator<Item = T::Value>,
+    {
+        self.write_value::<T>(&first)?;
+        self.write_values::<T, I>(rest)
+    }
+
+    /// Writes values of the given type to this container. Use this method when:
+    ///
+    /// * The element must occur once but may occur multiple times in the container.
+    /// * The element is restricted by allowed level, and not by allowed parent.
+    pub fn write_one_or_many_values_by_level<T, I>(
+        &mut self,
+        first: T::Value,
+        rest: I,
+    ) -> EbmlResult<()>
+    where
+        T: Element<Cardinality = cardinality::OneOrMany, AllowedParent = AnyContainer>,
+        T::MaxAllowedLevel: Exceeds<L>,
+        T::MinAllowedLevel: typenum::IsLessOrEqual<typenum::Sum<L, typenum::P1>, Output = typenum::True>,
+        I: IntoIterator<Item = T::Value>,
+    {
+        self.write_value::<T>(&first)?;
+        self.write_values::<T, I>(rest)
+    }
+
+    /// Starts a child container of the given type in this container. The child must be finished
+    /// (or dropped) before anything else can be written to this container. Use this method when
+    /// the child is restricted by allowed parent, and not by allowed level.
+    pub fn start_child_by_container<NC>(&mut self) -> EbmlResult<ChildWriter<'_, NC, L, S>>
+    where
+        NC: Container<MinAllowedLevel = AnyLevel, MaxAllowedLevel = AnyLevel, AllowedParent = C>,
+    {
+        self.start_child()
+    }
+
+    /// Starts a child container of the given type in this container. The child must be finished
+    /// (or dropped) before anything else can be written to this container. Use this method when
+    /// the child is restricted by allowed level, and not by allowed parent.
+    ///
+    /// The child may not have an unknown size, so the sink must be able to fill it in. On other
+    /// sinks, build the child in memory and use `write_child_by_level` instead.
+    pub fn start_child_by_level<NC>(&mut self) -> EbmlResult<ChildWriter<'_, NC, L, S>>
+    where
+        S: PatchableSink,
+        NC: Container<AllowedParent = AnyContainer>,
+        NC::MaxAllowedLevel: Exceeds<L>,
+        NC::MinAllowedLevel: typenum::IsLessOrEqual<typenum::Sum<L, typenum::P1>, Output = typenum::True>,
+    {
+        self.start_child()
+    }
+
+    /// Writes a child container which was built in memory to this container. Its size, and the
+    /// sizes of all containers inside it, are known regardless of the sink. Use this method when
+    /// the child is restricted by allowed parent, and not by allowed level.
+    pub fn write_child_by_container<NC>(
+        &mut self,
+        child: &ContainerImpl<NC, typenum::Sum<L, typenum::P1>>,
+    ) -> EbmlResult<()>
+    where
+        NC: Container<MinAllowedLevel = AnyLevel, MaxAllowedLevel = AnyLevel, AllowedParent = C>,
+    {
+        self.write_child(child)
+    }
+
+    /// Writes a child container which was built in memory to this container. Its size, and the
+    /// sizes of all containers inside it, are known regardless of the sink. Use this method when
+    /// the child is restricted by allowed level, and not by allowed parent.
+    pub fn write_child_by_level<NC>(
+        &mut self,
+        child: &ContainerImpl<NC, typenum::Sum<L, typenum::P1>>,
+    ) -> EbmlResult<()>
+    where
+        NC: Container<AllowedParent = AnyContainer>,
+        NC::MaxAllowedLevel: Exceeds<L>,
+        NC::MinAllowedLevel: typenum::IsLessOrEqual<typenum::Sum<L, typenum::P1>, Output = typenum::True>,
+    {
+        self.write_child(child)
     }
 
-    // TODO other child-reading methods
+    /// Writes a child container of the given type to this container, starting with a `Crc32`
+    /// element holding the checksum of the rest of the child. The child is written with the
+    /// writer passed to `write`, which buffers it in memory until the checksum is known, so its
+    /// size is known regardless of the sink. Use this method when the child is restricted by
+    /// allowed parent, and not by allowed level.
+    pub fn write_child_with_crc32_by_container<NC, F>(&mut self, write: F) -> EbmlResult<()>
+    where
+        NC: Container<MinAllowedLevel = AnyLevel, MaxAllowedLevel = AnyLevel, AllowedParent = C>,
+        F: FnOnce(&mut Crc32Writer<NC, typenum::Sum<L, typenum::P1>>) -> EbmlResult<()>,
+    {
+        self.write_child_with_crc32::<NC, F>(write)
+    }
+
+    /// Writes a child container of the given type to this container, starting with a `Crc32`
+    /// element holding the checksum of the rest of the child. The child is written with the
+    /// writer passed to `write`, which buffers it in memory until the checksum is known, so its
+    /// size is known regardless of the sink. Use this method when the child is restricted by
+    /// allowed level, and not by allowed parent.
+    pub fn write_child_with_crc32_by_level<NC, F>(&mut self, write: F) -> EbmlResult<()>
+    where
+        NC: Container<AllowedParent = AnyContainer>,
+        NC::MaxAllowedLevel: Exceeds<L>,
+        NC::MinAllowedLevel: typenum::IsLessOrEqual<typenum::Sum<L, typenum::P1>, Output = typenum::True>,
+        F: FnOnce(&mut Crc32Writer<NC, typenum::Sum<L, typenum::P1>>) -> EbmlResult<()>,
+    {
+        self.write_child_with_crc32::<NC, F>(write)
+    }
 }
 
-pub fn read_document<P: AsRef<Path>>(
-    path: P
-) -> EbmlResult<ContainerReader<EbmlHeader, typenum::Z0>> {
-    ContainerReader {
-        _c: PhantomData,
-        _l: PhantomData,
+/// Starts writing an EBML document. Top-level elements, starting with the `EbmlHeader`, are
+/// written as children of the returned `Document`.
+///
+/// Containers which are started in place are written with unknown sizes; use
+/// `write_seekable_document` or the `write_child_*` methods to write them with known sizes.
+pub fn write_document<W: Write>(
+    target: W,
+) -> ContainerWriter<Document, typenum::N1, Streaming<W>> {
+    ContainerWriter::new(Streaming::new(target), Vec::new(), None, Rc::default())
+}
+
+/// Starts writing an EBML document to a seekable writer. Top-level elements, starting with the
+/// `EbmlHeader`, are written as children of the returned `Document`.
+///
+/// The sizes of containers are filled in when they are finished.
+pub fn write_seekable_document<W: Write + Seek>(
+    target: W,
+) -> ContainerWriter<Document, typenum::N1, Seekable<W>> {
+    ContainerWriter::new(Seekable::new(target), Vec::new(), None, Rc::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use {child_order, root_container, AsciiStringValue, BinaryValue, ChildType, Id,
+         IntRangeRestriction, IntValue, Restriction, UintValue};
+    use std_containers::EbmlHeader;
+    use std_elems::{DocType, EbmlMaxSizeWidth, EbmlVersion};
+
+    // Root (0x1A45_0003), top level
+    //   Name: binary, exactly one (0x4101)
+    //   Item: container, zero or many (0x4103)
+    //     Value: int, exactly one, not negative (0x4104)
+    //     Global: uint, zero or many, any level (0x4107)
+    //     Info: container, zero or many, any level (0x4108)
+
+    #[derive(Debug)]
+    enum Root {}
+    impl Container for Root {
+        type Cardinality = cardinality::ExactlyOne;
+        type ChildOrder = child_order::Significant;
+        type AllowedParent = Document;
+        type MinAllowedLevel = AnyLevel;
+        type MaxAllowedLevel = AnyLevel;
+        const NAME: &'static str = "Root";
+
+        fn get_id() -> Id {
+            Id::from_encoded(0x1A45_0003).unwrap()
+        }
+
+        fn child_types() -> Vec<ChildType> {
+            vec![ChildType::element::<Name>(), ChildType::container::<Item>()]
+        }
+    }
+
+    #[derive(Debug)]
+    enum Item {}
+    impl Container for Item {
+        type Cardinality = cardinality::ZeroOrMany;
+        type ChildOrder = child_order::Significant;
+        type AllowedParent = Root;
+        type MinAllowedLevel = AnyLevel;
+        type MaxAllowedLevel = AnyLevel;
+        const NAME: &'static str = "Item";
+
+        fn get_id() -> Id {
+            Id::from_encoded(0x4103).unwrap()
+        }
+
+        fn child_types() -> Vec<ChildType> {
+            vec![
+                ChildType::element::<Value>(),
+                ChildType::element::<Global>(),
+                ChildType::container::<Info>(),
+            ]
+        }
+    }
+
+    #[derive(Debug)]
+    enum Info {}
+    impl Container for Info {
+        type Cardinality = cardinality::ZeroOrMany;
+        type ChildOrder = child_order::Significant;
+        type AllowedParent = AnyContainer;
+        type MinAllowedLevel = typenum::P1;
+        type MaxAllowedLevel = typenum::P4;
+        const NAME: &'static str = "Info";
+
+        fn get_id() -> Id {
+            Id::from_encoded(0x4108).unwrap()
+        }
+
+        fn child_types() -> Vec<ChildType> {
+            vec![ChildType::element::<Global>(), ChildType::container::<Info>()]
+        }
+    }
+
+    #[derive(Debug)]
+    enum Name {}
+    impl Element for Name {
+        type Value = BinaryValue;
+        type Cardinality = cardinality::ExactlyOne;
+        type AllowedParent = Root;
+        type MinAllowedLevel = AnyLevel;
+        type MaxAllowedLevel = AnyLevel;
+        const NAME: &'static str = "Name";
+
+        fn get_id() -> Id {
+            Id::from_encoded(0x4101).unwrap()
+        }
+    }
+
+    #[derive(Debug)]
+    enum Value {}
+    impl Element for Value {
+        type Value = IntValue;
+        type Cardinality = cardinality::ExactlyOne;
+        type AllowedParent = Item;
+        type MinAllowedLevel = AnyLevel;
+        type MaxAllowedLevel = AnyLevel;
+        const NAME: &'static str = "Value";
+
+        fn get_id() -> Id {
+            Id::from_encoded(0x4104).unwrap()
+        }
+
+        fn get_restrictions() -> Option<Box<Restriction<IntValue>>> {
+            Some(Box::new(IntRangeRestriction::OpenRight { min: 0 }))
+        }
+    }
+
+    #[derive(Debug)]
+    enum Global {}
+    impl Element for Global {
+        type Value = UintValue;
+        type Cardinality = cardinality::ZeroOrMany;
+        type AllowedParent = AnyContainer;
+        type MinAllowedLevel = typenum::P1;
+        type MaxAllowedLevel = typenum::P4;
+        const NAME: &'static str = "Global";
+
+        fn get_id() -> Id {
+            Id::from_encoded(0x4107).unwrap()
+        }
+    }
+
+    #[test]
+    fn write_values() {
+        let mut document = write_document(Vec::new());
+        {
+            let mut root = document.start_child_by_container::<Root>().unwrap();
+            root.write_exactly_one_value_by_container::<Name>(b"hi".into()).unwrap();
+            root.write_zero_or_many_values_by_level::<Global, _>(vec![1u8.into(), 256u16.into()])
+                .unwrap();
+            root.finish().unwrap();
+        }
+        let data = document.finish().unwrap().into_inner();
+
+        assert_eq!(vec![
+            0x1A, 0x45, 0x00, 0x03, 0xFF,
+                0x41, 0x01, 0x82, b'h', b'i',
+                0x41, 0x07, 0x81, 0x01,
+                0x41, 0x07, 0x82, 0x01, 0x00,
+        ], data);
+    }
+
+    #[test]
+    fn write_children() {
+        let mut document = write_document(Vec::new());
+        {
+            let mut root = document.start_child_by_container::<Root>().unwrap();
+            for i in 1..3 {
+                let mut item = root.start_child_by_container::<Item>().unwrap();
+                item.write_exactly_one_value_by_container::<Value>(IntValue::Int1(i)).unwrap();
+                item.write_zero_or_many_values_by_level::<Global, _>(vec![]).unwrap();
+            }
+        }
+        let data = document.finish().unwrap().into_inner();
+
+        assert_eq!(vec![
+            0x1A, 0x45, 0x00, 0x03, 0xFF,
+                0x41, 0x03, 0xFF,
+                    0x41, 0x04, 0x81, 0x01,
+                0x41, 0x03, 0xFF,
+                    0x41, 0x04, 0x81, 0x02,
+        ], data);
+    }
+
+    #[test]
+    fn write_crc32() {
+        let mut document = write_document(Vec::new());
+        {
+            let mut root = document.start_child_by_container::<Root>().unwrap();
+            root.write_exactly_one_value_by_container::<Name>(b"hi".into()).unwrap();
+            root.write_child_with_crc32_by_container::<Item, _>(|item| {
+                item.write_exactly_one_value_by_container::<Value>(IntValue::Int1(1))?;
+                // Children in the buffer get their sizes filled in.
+                let mut child = item.start_child_by_level::<Info>()?;
+                child.write_zero_or_many_values_by_level::<Global, _>(vec![2u8.into()])?;
+                child.finish().map(|_| ())
+            }).unwrap();
+            // The child must be complete.
+            match root.write_child_with_crc32_by_container::<Item, _>(|_| Ok(())) {
+                Err(EbmlError::CardinalityViolation("Value", context)) => {
+                    assert_eq!(Some(Value::get_id()), context.expected);
+                    assert_eq!(vec!["Root", "Item"], context.path);
+                }
+                other => panic!("unexpected result {:?}", other),
+            }
+        }
+        let data = document.finish().unwrap().into_inner();
+
+        let covered = [
+            0x41, 0x04, 0x81, 0x01,
+            0x41, 0x08, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04,
+                0x41, 0x07, 0x81, 0x02,
+        ];
+        let mut expected = vec![
+            0x1A, 0x45, 0x00, 0x03, 0xFF,
+                0x41, 0x01, 0x82, b'h', b'i',
+                0x41, 0x03, 0x98, 0xBF, 0x84,
+        ];
+        expected.extend_from_slice(&crc32::encode(crc32::checksum(&covered)));
+        expected.extend_from_slice(&covered);
+        assert_eq!(expected, data);
+    }
+
+    #[test]
+    fn round_trip_crc32() {
+        let mut document = write_document(Vec::new());
+        document.write_child_with_crc32_by_level::<EbmlHeader, _>(|header| {
+            header.write_zero_or_one_value_by_container::<EbmlVersion>(Some(1u8.into()))?;
+            header.write_zero_or_one_value_by_container::<DocType>(Some("webm".parse()?))
+        }).unwrap();
+        let mut data = document.finish().unwrap().into_inner();
+
+        let header = root_container(Cursor::new(data.clone())).unwrap();
+        assert_eq!(
+            Some(UintValue::Uint1(1)),
+            header.get_zero_or_one_value_by_container::<EbmlVersion>().unwrap()
+        );
+        assert_eq!(
+            "webm".parse::<AsciiStringValue>().ok(),
+            header.get_zero_or_one_value_by_container::<DocType>().unwrap()
+        );
+
+        data[14] = 2;
+        match root_container(Cursor::new(data)) {
+            Err(EbmlError::ChecksumMismatch(ref context)) if context.offset == Some(5) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[cfg(feature = "legacy-crc32")]
+    #[test]
+    fn write_legacy_crc32() {
+        let mut document = write_document(Vec::new());
+        {
+            let mut root = document.start_child_by_container::<Root>().unwrap();
+            root.write_crc32_container(|root| {
+                root.write_exactly_one_value_by_container::<Name>(b"hi".into())?;
+                let mut item = root.start_child_by_container::<Item>()?;
+                item.write_exactly_one_value_by_container::<Value>(IntValue::Int1(1))?;
+                item.finish().map(|_| ())
+            }).unwrap();
+            // Elements in the CRC-32 container count towards the cardinalities of the root.
+            match root.write_exactly_one_value_by_container::<Name>(b"hi".into()) {
+                Err(EbmlError::CardinalityViolation("Name", context)) => {
+                    assert_eq!(Some(Name::get_id()), context.actual);
+                    assert_eq!(vec!["Root"], context.path);
+                }
+                other => panic!("unexpected result {:?}", other),
+            }
+        }
+        let data = document.finish().unwrap().into_inner();
+
+        // Children in the buffer get their sizes filled in.
+        let covered = [
+            0x41, 0x01, 0x82, b'h', b'i',
+            0x41, 0x03, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04,
+                0x41, 0x04, 0x81, 0x01,
+        ];
+        let mut expected = vec![0x1A, 0x45, 0x00, 0x03, 0xFF, 0xC3, 0x9A, 0x42, 0xFE, 0x84];
+        expected.extend_from_slice(&crc32::encode(crc32::checksum(&covered)));
+        expected.extend_from_slice(&covered);
+        assert_eq!(expected, data);
+    }
+
+    #[cfg(feature = "legacy-crc32")]
+    #[test]
+    fn round_trip_legacy_crc32() {
+        let mut document = write_seekable_document(Cursor::new(Vec::new()));
+        {
+            let mut header = document.start_child_by_level::<EbmlHeader>().unwrap();
+            header.write_crc32_container(|header| {
+                header.write_zero_or_one_value_by_container::<EbmlVersion>(Some(1u8.into()))
+            }).unwrap();
+            let doc_type = "webm".parse().unwrap();
+            header.write_zero_or_one_value_by_container::<DocType>(Some(doc_type)).unwrap();
+            header.finish().unwrap();
+        }
+        let mut data = document.finish().unwrap().into_inner().into_inner();
+
+        let header = root_container(Cursor::new(data.clone())).unwrap();
+        assert_eq!(
+            Some(UintValue::Uint1(1)),
+            header.get_zero_or_one_value_by_container::<EbmlVersion>().unwrap()
+        );
+        assert_eq!(
+            "webm".parse::<AsciiStringValue>().ok(),
+            header.get_zero_or_one_value_by_container::<DocType>().unwrap()
+        );
+
+        // The CRC-32 value starts after the header's ID and reserved size, and its container's
+        // header.
+        data[24] = 2;
+        match root_container(Cursor::new(data)) {
+            Err(EbmlError::ChecksumMismatch(ref context)) if context.offset == Some(14) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut header = ContainerImpl::new();
+        header.push_value_by_container::<EbmlVersion>(1u8.into()).unwrap();
+        header.push_value_by_container::<DocType>("webm".parse().unwrap()).unwrap();
+        let mut document = write_document(Vec::new());
+        document.write_child_by_level::<EbmlHeader>(&header).unwrap();
+        let data = document.finish().unwrap().into_inner();
+
+        let header = root_container(Cursor::new(data)).unwrap();
+        assert_eq!(
+            Some(UintValue::Uint1(1)),
+            header.get_zero_or_one_value_by_container::<EbmlVersion>().unwrap()
+        );
+        assert_eq!(
+            "webm".parse::<AsciiStringValue>().ok(),
+            header.get_zero_or_one_value_by_container::<DocType>().unwrap()
+        );
+    }
+
+    #[test]
+    fn write_seekable() {
+        let mut document = write_seekable_document(Cursor::new(Vec::new()));
+        {
+            let mut root = document.start_child_by_container::<Root>().unwrap();
+            root.write_exactly_one_value_by_container::<Name>(b"".into()).unwrap();
+            {
+                let mut item = root.start_child_by_container::<Item>().unwrap();
+                item.write_exactly_one_value_by_container::<Value>(IntValue::Int1(1)).unwrap();
+                item.finish().unwrap();
+            }
+            // This child isn't finished, so it keeps its unknown size.
+            root.start_child_by_container::<Item>().unwrap();
+            root.finish().unwrap();
+        }
+        let data = document.finish().unwrap().into_inner().into_inner();
+
+        assert_eq!(vec![
+            0x1A, 0x45, 0x00, 0x03, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1B,
+                0x41, 0x01, 0x80,
+                0x41, 0x03, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04,
+                    0x41, 0x04, 0x81, 0x01,
+                0x41, 0x03, 0x01, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+        ], data);
+    }
+
+    #[test]
+    fn write_unseekable() {
+        // A writer which claims to be seekable, but can't actually seek.
+        #[derive(Debug)]
+        struct Pipe(Vec<u8>);
+        impl Write for Pipe {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.0.write(buf)
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+        impl Seek for Pipe {
+            fn seek(&mut self, _: SeekFrom) -> io::Result<u64> {
+                Err(io::Error::new(io::ErrorKind::Other, "illegal seek"))
+            }
+        }
+
+        let mut document = write_seekable_document(Pipe(Vec::new()));
+        {
+            let mut root = document.start_child_by_container::<Root>().unwrap();
+            root.write_exactly_one_value_by_container::<Name>(b"".into()).unwrap();
+            root.finish().unwrap();
+        }
+        let data = document.finish().unwrap().into_inner().0;
+
+        assert_eq!(vec![0x1A, 0x45, 0x00, 0x03, 0xFF, 0x41, 0x01, 0x80], data);
+
+        // Containers restricted by level may not have an unknown size.
+        let mut document = write_seekable_document(Pipe(Vec::new()));
+        match document.start_child_by_level::<EbmlHeader>() {
+            Err(EbmlError::UnknownSizeNotAllowed("EBML", _)) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+        // Nothing was written for the header.
+        {
+            let mut root = document.start_child_by_container::<Root>().unwrap();
+            root.write_exactly_one_value_by_container::<Name>(b"".into()).unwrap();
+            root.finish().unwrap();
+        }
+        let data = document.finish().unwrap().into_inner().0;
+        assert_eq!(vec![0x1A, 0x45, 0x00, 0x03, 0xFF, 0x41, 0x01, 0x80], data);
+    }
+
+    #[test]
+    fn write_flush() {
+        // A writer which counts how often it is flushed.
+        #[derive(Debug, Default)]
+        struct Counting(Vec<u8>, usize);
+        impl Write for Counting {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.0.write(buf)
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                self.1 += 1;
+                Ok(())
+            }
+        }
+
+        let mut document = write_document(Counting::default());
+        {
+            let mut root = document.start_child_by_container::<Root>().unwrap();
+            root.write_exactly_one_value_by_container::<Name>(b"".into()).unwrap();
+            {
+                let mut item = root.start_child_by_container::<Item>().unwrap();
+                item.write_exactly_one_value_by_container::<Value>(IntValue::Int1(1)).unwrap();
+                item.finish().unwrap();
+            }
+            root.finish().unwrap();
+        }
+        // Only finishing the document flushes the writer.
+        let target = document.finish().unwrap().into_inner();
+        assert_eq!(1, target.1);
+    }
+
+    #[test]
+    fn write_built_children() {
+        let mut item = ContainerImpl::new();
+        item.push_value_by_container::<Value>(IntValue::Int1(1)).unwrap();
+        item.push_value_by_level::<Global>(UintValue::Uint1(2)).unwrap();
+        let mut root = ContainerImpl::new();
+        root.push_value_by_container::<Name>(b"hi".into()).unwrap();
+        root.push_child_by_container::<Item>(item).unwrap();
+        let mut item = ContainerImpl::new();
+        item.push_value_by_container::<Value>(IntValue::Int1(3)).unwrap();
+        root.push_child_by_container::<Item>(item).unwrap();
+
+        let mut document = write_document(Vec::new());
+        document.write_child_by_container::<Root>(&root).unwrap();
+        let data = document.finish().unwrap().into_inner();
+
+        assert_eq!(vec![
+            0x1A, 0x45, 0x00, 0x03, 0x97,
+                0x41, 0x01, 0x82, b'h', b'i',
+                0x41, 0x03, 0x88,
+                    0x41, 0x04, 0x81, 0x01,
+                    0x41, 0x07, 0x81, 0x02,
+                0x41, 0x03, 0x84,
+                    0x41, 0x04, 0x81, 0x03,
+        ], data);
+    }
+
+    #[test]
+    fn write_limits() {
+        let mut document = write_seekable_document(Cursor::new(Vec::new()));
+        {
+            let mut header = document.start_child_by_level::<EbmlHeader>().unwrap();
+            let doc_type = "t".parse().unwrap();
+            header.write_zero_or_one_value_by_container::<DocType>(Some(doc_type)).unwrap();
+            let width = Some(UintValue::Uint1(2));
+            header.write_zero_or_one_value_by_container::<EbmlMaxSizeWidth>(width).unwrap();
+            header.finish().unwrap();
+        }
+        {
+            // Sizes are reserved with the declared width from now on.
+            let mut root = document.start_child_by_container::<Root>().unwrap();
+            match root.write_exactly_one_value_by_container::<Name>(vec![0; 0x4000].into()) {
+                Err(EbmlError::SizeOutOfRange(context)) => {
+                    assert_eq!(Some(Name::get_id()), context.actual);
+                    assert_eq!(vec!["Root"], context.path);
+                }
+                other => panic!("unexpected result {:?}", other),
+            }
+            root.write_exactly_one_value_by_container::<Name>(b"hi".into()).unwrap();
+            root.finish().unwrap();
+        }
+        let data = document.finish().unwrap().into_inner().into_inner();
+
+        assert_eq!(vec![
+            0x1A, 0x45, 0xDF, 0xA3, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08,
+                0x42, 0x82, 0x81, b't',
+                0x42, 0xF3, 0x81, 0x02,
+            0x1A, 0x45, 0x00, 0x03, 0x40, 0x05,
+                0x41, 0x01, 0x82, b'h', b'i',
+        ], data);
+
+        // Headers built in memory declare limits too.
+        let mut header = ContainerImpl::new();
+        header.push_value_by_container::<DocType>("t".parse().unwrap()).unwrap();
+        header.push_value_by_container::<EbmlMaxSizeWidth>(UintValue::Uint1(1)).unwrap();
+        let mut root = ContainerImpl::new();
+        root.push_value_by_container::<Name>(vec![0; 0x80].into()).unwrap();
+
+        let mut document = write_document(Vec::new());
+        document.write_child_by_level::<EbmlHeader>(&header).unwrap();
+        match document.write_child_by_container::<Root>(&root) {
+            Err(EbmlError::SizeOutOfRange(context)) => {
+                assert_eq!(Some(Root::get_id()), context.actual);
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert_eq!(13, document.finish().unwrap().into_inner().len());
+    }
+
+    #[test]
+    fn write_too_big_for_reserved_size() {
+        let mut document = write_seekable_document(Cursor::new(Vec::new()));
+        {
+            let mut header = document.start_child_by_level::<EbmlHeader>().unwrap();
+            let doc_type = "t".parse().unwrap();
+            header.write_zero_or_one_value_by_container::<DocType>(Some(doc_type)).unwrap();
+            let width = Some(UintValue::Uint1(1));
+            header.write_zero_or_one_value_by_container::<EbmlMaxSizeWidth>(width).unwrap();
+            header.finish().unwrap();
+        }
+
+        // Each child fits into a 1 byte size, but the root is longer than 126 bytes.
+        let mut root = document.start_child_by_container::<Root>().unwrap();
+        root.write_exactly_one_value_by_container::<Name>(vec![0; 120].into()).unwrap();
+        {
+            let mut item = root.start_child_by_container::<Item>().unwrap();
+            item.write_exactly_one_value_by_container::<Value>(IntValue::Int1(1)).unwrap();
+            item.finish().unwrap();
+        }
+        match root.finish() {
+            Err(EbmlError::SizeOutOfRange(context)) => {
+                assert_eq!(Some(Root::get_id()), context.actual);
+                assert!(context.path.is_empty());
+            }
+            other => panic!("unexpected result {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn restrictions() {
+        let mut document = write_document(Vec::new());
+        {
+            let mut root = document.start_child_by_container::<Root>().unwrap();
+            let mut item = root.start_child_by_container::<Item>().unwrap();
+            match item.write_exactly_one_value_by_container::<Value>(IntValue::Int1(-1)) {
+                Err(EbmlError::RestrictionViolation("Value", _)) => {}
+                other => panic!("unexpected result {:?}", other),
+            }
+            item.write_exactly_one_value_by_container::<Value>(IntValue::Int1(0)).unwrap();
+        }
+        assert_eq!(12, document.finish().unwrap().into_inner().len());
+
+        let mut item = ContainerImpl::<Item, typenum::P1>::new();
+        match item.push_value_by_container::<Value>(IntValue::Int1(-1)) {
+            Err(EbmlError::RestrictionViolation("Value", _)) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        // Document types which aren't printable ASCII can't even be created, so the restriction
+        // of `DocType` holds for every value which is written.
+        assert!("a\u{7F}".parse::<AsciiStringValue>().is_err());
+        let doc_type: AsciiStringValue = "webm".parse().unwrap();
+        assert!(DocType::get_restrictions().unwrap().matches(&doc_type));
+        let mut header = ContainerImpl::<EbmlHeader, typenum::Z0>::new();
+        header.push_value_by_container::<DocType>(doc_type.clone()).unwrap();
+        let mut document = write_seekable_document(Cursor::new(Vec::new()));
+        let mut writer = document.start_child_by_level::<EbmlHeader>().unwrap();
+        writer.write_zero_or_one_value_by_container::<DocType>(Some(doc_type)).unwrap();
+    }
+
+    #[test]
+    fn cardinality() {
+        let mut document = write_document(Vec::new());
+        let mut root = document.start_child_by_container::<Root>().unwrap();
+        root.write_exactly_one_value_by_container::<Name>(b"a".into()).unwrap();
+        match root.write_exactly_one_value_by_container::<Name>(b"b".into()) {
+            Err(EbmlError::CardinalityViolation("Name", _)) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        // Finishing a container without a required child fails.
+        let item = root.start_child_by_container::<Item>().unwrap();
+        match item.finish() {
+            Err(EbmlError::CardinalityViolation("Value", _)) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert!(root.write_child_by_container::<Item>(&ContainerImpl::new()).is_err());
+
+        let mut item = root.start_child_by_container::<Item>().unwrap();
+        item.write_exactly_one_value_by_container::<Value>(IntValue::Int1(1)).unwrap();
+        item.finish().unwrap();
+        root.finish().unwrap();
+
+        // Children which fail to be written don't count towards their cardinality.
+        let mut document = write_seekable_document(Cursor::new(Vec::new()));
+        let mut header = document.start_child_by_level::<EbmlHeader>().unwrap();
+        let width = Some(UintValue::Uint1(0));
+        assert!(header.write_zero_or_one_value_by_container::<EbmlMaxSizeWidth>(width).is_err());
+        let width = Some(UintValue::Uint1(1));
+        header.write_zero_or_one_value_by_container::<EbmlMaxSizeWidth>(width).unwrap();
+        header.write_zero_or_one_value_by_container::<DocType>(Some("t".parse().unwrap())).unwrap();
+        header.finish().unwrap();
 
-        source: File::open(path)?,
+        let mut root = ContainerImpl::new();
+        root.push_value_by_container::<Name>(vec![0; 0x80].into()).unwrap();
+        assert!(document.write_child_by_container::<Root>(&root).is_err());
+        let mut root = ContainerImpl::new();
+        root.push_value_by_container::<Name>(b"a".into()).unwrap();
+        document.write_child_by_container::<Root>(&root).unwrap();
     }
 }